@@ -0,0 +1,7 @@
+// Shared and static library exposing the C ABI of neatwork (see ../include/neatwork.h)
+//
+// The functions live in neatwork::ffi so the core crate itself stays an rlib and keeps building without std.
+
+extern crate neatwork;
+
+pub use neatwork::ffi::*;
@@ -0,0 +1,71 @@
+/* C interface of neatwork (build the ffi/ crate to get the shared or static library)
+ *
+ * Maintained by hand, the header_declares_all_functions test of src/ffi.rs checks that it declares every function.
+ */
+
+#ifndef NEATWORK_H
+#define NEATWORK_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Builds with the "single_precision" feature have to define NEATWORK_SINGLE_PRECISION */
+#ifdef NEATWORK_SINGLE_PRECISION
+typedef float neat_float;
+#else
+typedef double neat_float;
+#endif
+
+typedef enum NeatStatus {
+  NEAT_STATUS_OK = 0,
+  NEAT_STATUS_NULL_POINTER = 1,
+  NEAT_STATUS_INVALID_UTF8 = 2,
+  NEAT_STATUS_PARSE_ERROR = 3,
+  NEAT_STATUS_SIZE_MISMATCH = 4,
+  NEAT_STATUS_EVALUATION_ERROR = 5,
+  NEAT_STATUS_PANIC = 6,
+  NEAT_STATUS_STATE_MISMATCH = 7,
+} NeatStatus;
+
+/* Opaque handle of a network */
+typedef struct NeatNetwork NeatNetwork;
+
+/* Static, null terminated description of a status */
+const uint8_t *neat_status_message(uint32_t status);
+
+/* Parses a network from a JSON buffer (as written by Network::export()) and stores the new handle in out */
+NeatStatus neat_network_load_json(const uint8_t *data, size_t length, NeatNetwork **out);
+
+NeatStatus neat_network_input_count(const NeatNetwork *network, size_t *out);
+
+NeatStatus neat_network_output_count(const NeatNetwork *network, size_t *out);
+
+/* Evaluates the network once, the buffer lengths have to match the input and output counts */
+NeatStatus neat_network_evaluate(NeatNetwork *network,
+                                 const neat_float *inputs,
+                                 size_t input_length,
+                                 neat_float *outputs,
+                                 size_t output_length);
+
+/* Removes all recurrent data of the network */
+NeatStatus neat_network_reset(NeatNetwork *network);
+
+/* Copies the network including its recurrent state into a new handle */
+NeatStatus neat_network_snapshot(const NeatNetwork *network, NeatNetwork **out);
+
+/* Replaces the recurrent state (node outputs and memory cell contents) of the network with the one of a snapshot taken from it
+ * (snapshots of any other network, even one loaded from the same JSON, are rejected) */
+NeatStatus neat_network_restore(NeatNetwork *network, const NeatNetwork *snapshot);
+
+/* Releases a network handle (null is ignored) */
+void neat_network_free(NeatNetwork *network);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* NEATWORK_H */
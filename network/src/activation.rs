@@ -0,0 +1,93 @@
+#[cfg(feature = "training")]
+use rand::{thread_rng, Rng};
+use Float;
+use math;
+
+/// Function that is applied to the aggregated input of a node to calculate its output
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(RustcDecodable, RustcEncodable))]
+pub enum Activation {
+    Relu,
+    /// Steepened sigmoid as proposed in the original NEAT paper
+    Sigmoid,
+    Tanh,
+    Identity,
+    Gaussian,
+    Sin,
+    Abs
+}
+
+impl Activation {
+    /// List of all available activation functions
+    pub fn all() -> [Activation; 7] {
+        [
+            Activation::Relu,
+            Activation::Sigmoid,
+            Activation::Tanh,
+            Activation::Identity,
+            Activation::Gaussian,
+            Activation::Sin,
+            Activation::Abs
+        ]
+    }
+
+    /// Picks a random activation function
+    #[cfg(feature = "training")]
+    pub fn random() -> Activation {
+        *thread_rng().choose(&Activation::all()).unwrap()
+    }
+
+    /// Lowercase name of the activation function
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Activation::Relu => "relu",
+            Activation::Sigmoid => "sigmoid",
+            Activation::Tanh => "tanh",
+            Activation::Identity => "identity",
+            Activation::Gaussian => "gaussian",
+            Activation::Sin => "sin",
+            Activation::Abs => "abs"
+        }
+    }
+
+    /// Looks up an activation function by its name()
+    pub fn from_name(name: &str) -> Option<Activation> {
+        Activation::all().iter().find(|activation| activation.name() == name).cloned()
+    }
+
+    pub fn apply(&self, x: Float) -> Float {
+        match *self {
+            Activation::Relu => relu(x),
+            Activation::Sigmoid => steep_sigmoid(x),
+            Activation::Tanh => math::tanh(x),
+            Activation::Identity => x,
+            Activation::Gaussian => math::exp(-x * x),
+            Activation::Sin => math::sin(x),
+            Activation::Abs => math::abs(x)
+        }
+    }
+}
+
+impl Default for Activation {
+    fn default() -> Activation {
+        Activation::Relu
+    }
+}
+
+fn relu(x: Float) -> Float {
+    if x > 0.0 { x } else { 0.0 }
+}
+
+/// Steepened sigmoid function
+fn steep_sigmoid(x: Float) -> Float {
+    1.0 / ( 1.0 + math::exp(-4.9 * x))
+}
+
+#[test]
+fn sigmoid() {
+    if cfg!(feature = "single_precision") {
+        assert_eq!(steep_sigmoid(0.25), 0.77294225)
+    } else if cfg!(feature = "double_precision") {
+        assert_eq!(steep_sigmoid(0.25), 0.7729422593967386);
+    }
+}
@@ -0,0 +1,134 @@
+#[cfg(feature = "training")]
+use rand::{thread_rng, Rng};
+use Float;
+use math;
+
+/// Function that combines all inputs of a node into a single value before the activation function is applied
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(RustcDecodable, RustcEncodable))]
+pub enum Aggregation {
+    Sum,
+    Product,
+    Max,
+    Min,
+    Mean,
+    Median,
+    /// Input with the largest absolute value (keeping its sign)
+    MaxAbs
+}
+
+impl Aggregation {
+    /// List of all available aggregation functions
+    pub fn all() -> [Aggregation; 7] {
+        [
+            Aggregation::Sum,
+            Aggregation::Product,
+            Aggregation::Max,
+            Aggregation::Min,
+            Aggregation::Mean,
+            Aggregation::Median,
+            Aggregation::MaxAbs
+        ]
+    }
+
+    /// Picks a random aggregation function
+    #[cfg(feature = "training")]
+    pub fn random() -> Aggregation {
+        Aggregation::random_with_rng(&mut thread_rng())
+    }
+
+    /// Picks a random aggregation function using the given generator
+    #[cfg(feature = "training")]
+    pub fn random_with_rng<R: Rng>(rng: &mut R) -> Aggregation {
+        *rng.choose(&Aggregation::all()).unwrap()
+    }
+
+    /// Lowercase name of the aggregation function
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Aggregation::Sum => "sum",
+            Aggregation::Product => "product",
+            Aggregation::Max => "max",
+            Aggregation::Min => "min",
+            Aggregation::Mean => "mean",
+            Aggregation::Median => "median",
+            Aggregation::MaxAbs => "maxabs"
+        }
+    }
+
+    /// Looks up an aggregation function by its name()
+    pub fn from_name(name: &str) -> Option<Aggregation> {
+        Aggregation::all().iter().find(|aggregation| aggregation.name() == name).cloned()
+    }
+
+    /// Combines the inputs into a single value. A node without any inputs always aggregates to zero.
+    #[cfg(feature = "alloc")]
+    pub fn aggregate(&self, inputs: &[Float]) -> Float {
+        match *self {
+            Aggregation::Median => median(&mut inputs.to_vec()),
+            _ => self.aggregate_unordered(inputs)
+        }
+    }
+
+    /// Like aggregate() but sorts the inputs for the median instead of copying them (which works without alloc)
+    pub fn aggregate_in_place(&self, inputs: &mut [Float]) -> Float {
+        match *self {
+            Aggregation::Median => median(inputs),
+            _ => self.aggregate_unordered(inputs)
+        }
+    }
+
+    /// Aggregation of everything but the median
+    fn aggregate_unordered(&self, inputs: &[Float]) -> Float {
+        if inputs.is_empty() { return 0.0 }
+
+        match *self {
+            Aggregation::Sum => inputs.iter().fold(0.0, |acc, input| acc + input),
+            Aggregation::Product => inputs.iter().fold(1.0, |acc, input| acc * input),
+            Aggregation::Max => inputs.iter().fold(inputs[0], |acc, input| acc.max(*input)),
+            Aggregation::Min => inputs.iter().fold(inputs[0], |acc, input| acc.min(*input)),
+            Aggregation::Mean => inputs.iter().fold(0.0, |acc, input| acc + input) / inputs.len() as Float,
+            Aggregation::Median => unreachable!("The median has to sort its inputs"),
+            Aggregation::MaxAbs => inputs.iter().fold(inputs[0], |acc, input| {
+                if math::abs(*input) > math::abs(acc) { *input } else { acc }
+            })
+        }
+    }
+}
+
+fn median(inputs: &mut [Float]) -> Float {
+    if inputs.is_empty() { return 0.0 }
+
+    inputs.sort_unstable_by(|a, b| a.partial_cmp(b).expect("Float comparison failed"));
+    let middle = inputs.len() / 2;
+    if inputs.len() % 2 == 0 {
+        (inputs[middle - 1] + inputs[middle]) / 2.0
+    } else {
+        inputs[middle]
+    }
+}
+
+impl Default for Aggregation {
+    fn default() -> Aggregation {
+        Aggregation::Sum
+    }
+}
+
+#[test]
+fn aggregate() {
+    let inputs = [3.0, -4.0, 1.0, 2.0];
+    assert_eq!(Aggregation::Sum.aggregate(&inputs), 2.0);
+    assert_eq!(Aggregation::Product.aggregate(&inputs), -24.0);
+    assert_eq!(Aggregation::Max.aggregate(&inputs), 3.0);
+    assert_eq!(Aggregation::Min.aggregate(&inputs), -4.0);
+    assert_eq!(Aggregation::Mean.aggregate(&inputs), 0.5);
+    assert_eq!(Aggregation::Median.aggregate(&inputs), 1.5);
+    assert_eq!(Aggregation::MaxAbs.aggregate(&inputs), -4.0);
+}
+
+#[test]
+fn aggregate_empty() {
+    for aggregation in Aggregation::all().iter() {
+        assert_eq!(aggregation.aggregate(&[]), 0.0);
+    }
+}
@@ -0,0 +1,136 @@
+// Estimates how much the outputs of a network depend on each of its inputs.
+//
+// All methods return a matrix indexed by [input][output]. The sample based methods evaluate a copy of the network
+// that is reset before every evaluation, so the results don't depend on (or change) the state of the network.
+
+use rand::{thread_rng, Rng};
+
+use {
+    NID,
+    Float,
+    Network,
+    EvaluationError
+};
+
+impl Network {
+    fn evaluate_fresh(&self, copy: &mut Network, inputs: &Vec<Float>) -> Result<Vec<Float>, EvaluationError> {
+        copy.reset();
+        copy.evaluate(inputs)
+    }
+
+    /// Mean absolute derivative of every output with respect to every input over the samples
+    ///
+    /// The derivatives are estimated with central differences of the given step size.
+    pub fn sensitivity(&self, samples: &[Vec<Float>], step: Float) -> Result<Vec<Vec<Float>>, EvaluationError> {
+        let mut copy = self.clone();
+        let mut result = vec![vec![0.0; self.outputs.len()]; self.inputs];
+        for sample in samples.iter() {
+            for input in 0..self.inputs {
+                let mut inputs = sample.clone();
+                if inputs.len() != self.inputs {
+                    return Err(EvaluationError::InputSizeMismatch);
+                }
+                inputs[input] = sample[input] + step;
+                let upper = self.evaluate_fresh(&mut copy, &inputs)?;
+                inputs[input] = sample[input] - step;
+                let lower = self.evaluate_fresh(&mut copy, &inputs)?;
+                for output in 0..self.outputs.len() {
+                    result[input][output] += ((upper[output] - lower[output]) / (2.0 * step)).abs();
+                }
+            }
+        }
+        Ok(normalize(result, samples.len()))
+    }
+
+    /// Mean squared change of every output when the values of one input are shuffled between the samples
+    ///
+    /// Each input gets shuffled the given amount of times and the changes are averaged over all repeats.
+    pub fn permutation_importance(&self, samples: &[Vec<Float>], repeats: usize) -> Result<Vec<Vec<Float>>, EvaluationError> {
+        self.permutation_importance_with_rng(samples, repeats, &mut thread_rng())
+    }
+
+    /// Like permutation_importance() but shuffles with the given generator
+    pub fn permutation_importance_with_rng<R: Rng>(&self, samples: &[Vec<Float>], repeats: usize, rng: &mut R)
+        -> Result<Vec<Vec<Float>>, EvaluationError>
+    {
+        let mut copy = self.clone();
+        let reference = samples.iter().map(|sample| self.evaluate_fresh(&mut copy, sample)).collect::<Result<Vec<_>, _>>()?;
+
+        let mut result = vec![vec![0.0; self.outputs.len()]; self.inputs];
+        for input in 0..self.inputs {
+            for _ in 0..repeats {
+                let mut column = samples.iter().map(|sample| sample[input]).collect::<Vec<_>>();
+                rng.shuffle(&mut column);
+                for ((sample, value), expected) in samples.iter().zip(column.into_iter()).zip(reference.iter()) {
+                    let mut inputs = sample.clone();
+                    inputs[input] = value;
+                    let outputs = self.evaluate_fresh(&mut copy, &inputs)?;
+                    for output in 0..self.outputs.len() {
+                        result[input][output] += (outputs[output] - expected[output]).powi(2);
+                    }
+                }
+            }
+        }
+        Ok(normalize(result, samples.len() * repeats))
+    }
+
+    /// Whether a path of enabled genes leads from the input to the output
+    pub fn reachability(&self) -> Vec<Vec<bool>> {
+        (0..self.inputs).map(|input| {
+            let mut reached = vec![false; self.nodes.len()];
+            let mut stack: Vec<NID> = vec![input];
+            reached[input] = true;
+            while let Some(node) = stack.pop() {
+                for gene in self.genome.iter().filter(|gene| !gene.disabled && gene.link.0 == node) {
+                    if !reached[gene.link.1] {
+                        reached[gene.link.1] = true;
+                        stack.push(gene.link.1);
+                    }
+                }
+            }
+            self.outputs.iter().map(|output| reached[*output]).collect()
+        }).collect()
+    }
+}
+
+fn normalize(mut matrix: Vec<Vec<Float>>, count: usize) -> Vec<Vec<Float>> {
+    if count > 0 {
+        for value in matrix.iter_mut().flat_map(|row| row.iter_mut()) {
+            *value /= count as Float;
+        }
+    }
+    matrix
+}
+
+#[cfg(test)]
+fn two_input_network() -> Network {
+    use {NetworkBuilder, Activation};
+
+    // y0 only depends on x0, y1 on nothing, x2 only has a disabled connection
+    NetworkBuilder::new()
+        .input("x0").input("x1").input("x2").output("y0").output("y1")
+        .activation("x0", Activation::Identity).activation("y0", Activation::Identity)
+        .connect("x0", "y0", 2.0).connect("x1", "y1", 0.0).connect_disabled("x2", "y0", 1.0)
+        .build().unwrap()
+}
+
+#[test]
+fn input_importance() {
+    use rand::{SeedableRng, XorShiftRng};
+
+    let net = two_input_network();
+    let samples = vec![vec![0.5, 0.2, 1.0], vec![1.0, 0.4, -1.0], vec![-0.5, 0.9, 0.0]];
+
+    let sensitivity = net.sensitivity(&samples, 1e-3).unwrap();
+    assert!((sensitivity[0][0] - 2.0).abs() < 1e-3);
+    assert_eq!((sensitivity[1][1], sensitivity[2][0]), (0.0, 0.0));
+
+    let importance = net.permutation_importance(&samples, 10).unwrap();
+    assert!(importance[0][0] > 0.0);
+    assert_eq!((importance[1][0], importance[1][1], importance[2][0]), (0.0, 0.0, 0.0));
+    let seeded = || XorShiftRng::from_seed([1, 2, 3, 4]);
+    assert_eq!(net.permutation_importance_with_rng(&samples, 3, &mut seeded()).unwrap(),
+               net.permutation_importance_with_rng(&samples, 3, &mut seeded()).unwrap());
+
+    assert_eq!(net.reachability(), vec![vec![true, false], vec![false, true], vec![false, false]]);
+}
@@ -0,0 +1,407 @@
+use std::mem;
+
+use {
+    NID,
+    Float,
+    Network,
+    Node,
+    Gene,
+    Gate,
+    MemoryCell,
+    Activation,
+    Aggregation
+};
+
+const MAGIC: &'static [u8; 4] = b"NEAT";
+const VERSION: u8 = 1;
+const KIND_NETWORK: u8 = 0;
+const KIND_POPULATION: u8 = 1;
+
+const NODE_MEMORY: u8 = 1 << 6;
+const NODE_BIAS: u8 = 1 << 7;
+
+#[derive(Debug, PartialEq)]
+pub enum BinaryError {
+    /// The data doesn't start with the magic bytes
+    InvalidMagic,
+    UnsupportedVersion(u8),
+    /// A population has been decoded as a single network or vice versa
+    WrongKind,
+    ChecksumMismatch,
+    UnexpectedEnd,
+    /// The data is structurally broken (description of the problem)
+    Invalid(String)
+}
+
+/// Compact binary encoding of networks
+///
+/// Layout: magic "NEAT", version, size of a Float in bytes, kind (network or population), payload and a CRC-32 of
+/// everything before it. NIDs and counts are stored as varints, weights and biases as raw little endian Floats,
+/// activation, aggregation and memory flags of a node share one byte and the gates and disabled flags of the genes
+/// are bit-packed behind the genes. Only the structure is stored, decoded networks have been reset.
+/// Data written with a different Float precision is converted while decoding.
+impl Network {
+    pub fn to_binary(&self) -> Vec<u8> {
+        let mut writer = Writer::new(KIND_NETWORK);
+        writer.write_network(self);
+        writer.finish()
+    }
+
+    pub fn from_binary(data: &[u8]) -> Result<Network, BinaryError> {
+        let mut reader = Reader::new(data, KIND_NETWORK)?;
+        let network = reader.read_network()?;
+        reader.finish()?;
+        Ok(network)
+    }
+}
+
+/// Encodes multiple networks (e.g. a whole population) into a single checksummed blob
+pub fn encode_population(networks: &[Network]) -> Vec<u8> {
+    let mut writer = Writer::new(KIND_POPULATION);
+    writer.write_varint(networks.len() as u64);
+    for network in networks.iter() {
+        writer.write_network(network);
+    }
+    writer.finish()
+}
+
+pub fn decode_population(data: &[u8]) -> Result<Vec<Network>, BinaryError> {
+    let mut reader = Reader::new(data, KIND_POPULATION)?;
+    let count = reader.read_varint()?;
+    let mut networks = Vec::new();
+    for _ in 0..count {
+        networks.push(reader.read_network()?);
+    }
+    reader.finish()?;
+    Ok(networks)
+}
+
+struct Writer {
+    data: Vec<u8>
+}
+
+impl Writer {
+    fn new(kind: u8) -> Writer {
+        let mut data = MAGIC.to_vec();
+        data.push(VERSION);
+        data.push(mem::size_of::<Float>() as u8);
+        data.push(kind);
+        Writer { data: data }
+    }
+
+    fn write_varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.data.push((value as u8 & 0x7f) | 0x80);
+            value >>= 7;
+        }
+        self.data.push(value as u8);
+    }
+
+    fn write_float(&mut self, value: Float) {
+        let (bits, size) = if mem::size_of::<Float>() == 8 {
+            ((value as f64).to_bits(), 8)
+        } else {
+            ((value as f32).to_bits() as u64, 4)
+        };
+        for byte in 0..size {
+            self.data.push((bits >> (byte * 8)) as u8);
+        }
+    }
+
+    fn write_bits(&mut self, values: &[u8], bits: usize) {
+        let per_byte = 8 / bits;
+        for chunk in values.chunks(per_byte) {
+            self.data.push(chunk.iter().enumerate().fold(0, |byte, (i, value)| byte | (value << (i * bits))));
+        }
+    }
+
+    fn write_network(&mut self, network: &Network) {
+        self.write_varint(network.inputs as u64);
+        self.write_varint(network.outputs.len() as u64);
+        for output in network.outputs.iter() {
+            self.write_varint(*output as u64);
+        }
+
+        self.write_varint(network.nodes.len() as u64);
+        for node in network.nodes.iter() {
+            let activation = Activation::all().iter().position(|activation| *activation == node.activation).unwrap() as u8;
+            let aggregation = Aggregation::all().iter().position(|aggregation| *aggregation == node.aggregation).unwrap() as u8;
+            let mut flags = activation | (aggregation << 3);
+            if node.is_memory() { flags |= NODE_MEMORY }
+            if node.bias != 0.0 { flags |= NODE_BIAS }
+            self.data.push(flags);
+            if node.bias != 0.0 {
+                self.write_float(node.bias);
+            }
+        }
+
+        self.write_varint(network.genome.len() as u64);
+        for gene in network.genome.iter() {
+            self.write_varint(gene.link.0 as u64);
+            self.write_varint(gene.link.1 as u64);
+            self.write_float(gene.weight);
+        }
+        let gates = network.genome.iter().map(|gene| {
+            gene.gate.map_or(0, |gate| Gate::all().iter().position(|other| *other == gate).unwrap() as u8 + 1)
+        }).collect::<Vec<_>>();
+        self.write_bits(&gates, 2);
+        let disabled = network.genome.iter().map(|gene| gene.disabled as u8).collect::<Vec<_>>();
+        self.write_bits(&disabled, 1);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        let checksum = crc32(&self.data);
+        for byte in 0..4 {
+            self.data.push((checksum >> (byte * 8)) as u8);
+        }
+        self.data
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+    float_size: usize
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8], kind: u8) -> Result<Reader<'a>, BinaryError> {
+        if data.len() < MAGIC.len() || &data[..MAGIC.len()] != MAGIC {
+            return Err(BinaryError::InvalidMagic);
+        }
+        if data.len() < MAGIC.len() + 3 + 4 {
+            return Err(BinaryError::UnexpectedEnd);
+        }
+        if data[4] != VERSION {
+            return Err(BinaryError::UnsupportedVersion(data[4]));
+        }
+
+        let (content, checksum) = data.split_at(data.len() - 4);
+        let checksum = checksum.iter().enumerate().fold(0, |acc, (byte, value)| acc | (*value as u32) << (byte * 8));
+        if crc32(content) != checksum {
+            return Err(BinaryError::ChecksumMismatch);
+        }
+
+        let float_size = data[5] as usize;
+        if float_size != 4 && float_size != 8 {
+            return Err(BinaryError::Invalid(format!("unknown float size {}", float_size)));
+        }
+        if data[6] != kind {
+            return Err(BinaryError::WrongKind);
+        }
+
+        Ok(Reader {
+            data: content,
+            position: 7,
+            float_size: float_size
+        })
+    }
+
+    fn read_byte(&mut self) -> Result<u8, BinaryError> {
+        let byte = *self.data.get(self.position).ok_or(BinaryError::UnexpectedEnd)?;
+        self.position += 1;
+        Ok(byte)
+    }
+
+    fn read_varint(&mut self) -> Result<u64, BinaryError> {
+        let mut value = 0;
+        for shift in 0..10 {
+            let byte = self.read_byte()?;
+            value |= ((byte & 0x7f) as u64) << (shift * 7);
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(BinaryError::Invalid("varint too long".to_string()))
+    }
+
+    /// Reads a NID and makes sure it is smaller than the node count
+    fn read_nid(&mut self, nodes: usize) -> Result<NID, BinaryError> {
+        let nid = self.read_varint()? as NID;
+        if nid >= nodes {
+            return Err(BinaryError::Invalid(format!("node {} out of range", nid)));
+        }
+        Ok(nid)
+    }
+
+    fn read_float(&mut self) -> Result<Float, BinaryError> {
+        let mut bits = 0u64;
+        for byte in 0..self.float_size {
+            bits |= (self.read_byte()? as u64) << (byte * 8);
+        }
+        Ok(if self.float_size == 8 {
+            f64::from_bits(bits) as Float
+        } else {
+            f32::from_bits(bits as u32) as Float
+        })
+    }
+
+    fn read_bits(&mut self, count: usize, bits: usize) -> Result<Vec<u8>, BinaryError> {
+        let per_byte = 8 / bits;
+        let mask = (1 << bits) - 1;
+        let mut values = Vec::with_capacity(count);
+        while values.len() < count {
+            let byte = self.read_byte()?;
+            for i in 0..per_byte {
+                if values.len() == count { break }
+                values.push((byte >> (i * bits)) & mask);
+            }
+        }
+        Ok(values)
+    }
+
+    fn read_network(&mut self) -> Result<Network, BinaryError> {
+        let inputs = self.read_varint()? as usize;
+        let output_count = self.read_varint()? as usize;
+        let mut outputs = Vec::new();
+        for _ in 0..output_count {
+            outputs.push(self.read_varint()? as NID);
+        }
+
+        let node_count = self.read_varint()? as usize;
+        if inputs > node_count || outputs.iter().any(|output| *output >= node_count) {
+            return Err(BinaryError::Invalid("input or output out of range".to_string()));
+        }
+        let mut nodes = Vec::new();
+        for _ in 0..node_count {
+            let flags = self.read_byte()?;
+            let mut node = Node::new();
+            node.activation = *Activation::all().get((flags & 0x7) as usize).ok_or_else(|| {
+                BinaryError::Invalid(format!("unknown activation {}", flags & 0x7))
+            })?;
+            node.aggregation = *Aggregation::all().get(((flags >> 3) & 0x7) as usize).ok_or_else(|| {
+                BinaryError::Invalid(format!("unknown aggregation {}", (flags >> 3) & 0x7))
+            })?;
+            if flags & NODE_MEMORY != 0 {
+                node.memory = Some(MemoryCell::new());
+            }
+            if flags & NODE_BIAS != 0 {
+                node.bias = self.read_float()?;
+            }
+            nodes.push(node);
+        }
+
+        let gene_count = self.read_varint()? as usize;
+        let mut genome = Vec::new();
+        for _ in 0..gene_count {
+            let src = self.read_nid(node_count)?;
+            let dest = self.read_nid(node_count)?;
+            genome.push(Gene::with_weight(src, dest, false, self.read_float()?));
+        }
+        for (gene, gate) in genome.iter_mut().zip(self.read_bits(gene_count, 2)?.into_iter()) {
+            if gate > 0 {
+                gene.gate = Some(Gate::all()[gate as usize - 1]);
+            }
+        }
+        for (gene, disabled) in genome.iter_mut().zip(self.read_bits(gene_count, 1)?.into_iter()) {
+            gene.disabled = disabled == 1;
+        }
+
+        Ok(Network {
+            genome: genome,
+            nodes: nodes,
+            inputs: inputs,
+            outputs: outputs
+        })
+    }
+
+    fn finish(self) -> Result<(), BinaryError> {
+        if self.position == self.data.len() {
+            Ok(())
+        } else {
+            Err(BinaryError::Invalid("trailing data".to_string()))
+        }
+    }
+}
+
+/// CRC-32 (IEEE 802.3) checksum
+fn crc32(data: &[u8]) -> u32 {
+    let table = (0..256).map(|i| {
+        (0..8).fold(i as u32, |crc, _| if crc & 1 == 1 { 0xedb88320 ^ (crc >> 1) } else { crc >> 1 })
+    }).collect::<Vec<_>>();
+    !data.iter().fold(!0, |crc, byte| table[((crc ^ *byte as u32) & 0xff) as usize] ^ (crc >> 8))
+}
+
+#[cfg(test)]
+fn test_network() -> Network {
+    let mut net = Network::new_empty(3, 2);
+    net.nodes.push(Node::new_memory());
+    net.nodes[5].bias = -0.25;
+    net.nodes[5].activation = Activation::Tanh;
+    net.nodes[3].aggregation = Aggregation::Median;
+    net.genome.push(Gene::gate_with_weight(0, 5, Gate::Output, 0.75));
+    net.genome.push(Gene::with_weight(5, 3, true, 1.5));
+    net.genome[1].disable();
+    net
+}
+
+#[test]
+fn round_trip_binary() {
+    let net = test_network();
+    assert_eq!(crc32(b"123456789"), 0xcbf43926);
+    assert_eq!(Network::from_binary(&net.to_binary()).unwrap(), net);
+
+    let population = vec![net.clone(), Network::new_empty(1, 1), net];
+    assert_eq!(decode_population(&encode_population(&population)).unwrap(), population);
+}
+
+#[test]
+fn corrupted_binary() {
+    let mut data = test_network().to_binary();
+    assert_eq!(decode_population(&data), Err(BinaryError::WrongKind));
+    data[10] ^= 0x01;
+    assert_eq!(Network::from_binary(&data), Err(BinaryError::ChecksumMismatch));
+    assert_eq!(Network::from_binary(b"JSON{}"), Err(BinaryError::InvalidMagic));
+}
+
+/// Population with random extra genes for the comparisons with JSON (which need the JSON encoder of the
+/// serialization feature and the random genes of the training feature, so they only run with both)
+#[cfg(all(test, feature = "serialization", feature = "training"))]
+fn benchmark_population(size: usize) -> Vec<Network> {
+    (0..size).map(|_| {
+        let mut net = test_network();
+        for gene_id in 0..net.genome.len() {
+            net.genome.push(Gene::random(gene_id % net.nodes.len(), 5, false));
+        }
+        net
+    }).collect()
+}
+
+#[cfg(all(feature = "serialization", feature = "training"))]
+#[test]
+fn smaller_than_json() {
+    use rustc_serialize::json;
+
+    let population = benchmark_population(50);
+    let json_data = json::encode(&population).unwrap();
+    let binary_data = encode_population(&population);
+    assert_eq!(decode_population(&binary_data).unwrap(), population);
+    assert!(binary_data.len() * 4 < json_data.len());
+}
+
+/// Times encoding and decoding of both formats (ignored by default as wall clock time is too noisy for CI, run it
+/// with cargo test --release -- --ignored faster_than_json)
+#[cfg(all(feature = "serialization", feature = "training"))]
+#[test]
+#[ignore]
+fn faster_than_json() {
+    use std::time::Instant;
+    use rustc_serialize::json;
+
+    let population = benchmark_population(500);
+
+    let start = Instant::now();
+    let json_data = json::encode(&population).unwrap();
+    let json_decoded: Vec<Network> = json::decode(&json_data).unwrap();
+    let json_time = start.elapsed();
+
+    let start = Instant::now();
+    let binary_data = encode_population(&population);
+    let binary_decoded = decode_population(&binary_data).unwrap();
+    let binary_time = start.elapsed();
+
+    println!("JSON: {} bytes in {:?}, binary: {} bytes in {:?}", json_data.len(), json_time, binary_data.len(), binary_time);
+    assert_eq!(json_decoded.len(), binary_decoded.len());
+    assert_eq!(binary_decoded, population);
+    assert!(binary_time < json_time);
+}
@@ -0,0 +1,228 @@
+use {
+    NID,
+    Float,
+    Network,
+    Node,
+    Gene,
+    Gate,
+    MemoryCell,
+    Activation,
+    Aggregation
+};
+
+#[derive(Debug, PartialEq)]
+pub enum BuildError {
+    /// A node name has been used more than once
+    DuplicateName(String),
+    /// A connection or setting refers to a node that has not been declared
+    UnknownNode(String),
+    /// The same connection (to the same gate) has been declared more than once
+    DuplicateConnection(String, String),
+    /// A connection targets a gate of a node that is not a memory cell
+    NotAMemoryCell(String),
+    NoOutputs
+}
+
+enum Setting {
+    Activation(Activation),
+    Aggregation(Aggregation),
+    Bias(Float),
+    Memory
+}
+
+struct Connection {
+    src: String,
+    dest: String,
+    gate: Option<Gate>,
+    weight: Float,
+    disabled: bool
+}
+
+/// Fluent builder for hand-designed networks that refers to nodes by name
+///
+/// The NIDs are assigned in the order inputs, outputs, hidden nodes (each in declaration order),
+/// which matches the layout of networks created with Network::new_empty().
+/// All the validation is deferred until build() is called.
+pub struct NetworkBuilder {
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+    hidden: Vec<String>,
+    settings: Vec<(String, Setting)>,
+    connections: Vec<Connection>
+}
+
+impl NetworkBuilder {
+    pub fn new() -> NetworkBuilder {
+        NetworkBuilder {
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            hidden: Vec::new(),
+            settings: Vec::new(),
+            connections: Vec::new()
+        }
+    }
+
+    pub fn input(mut self, name: &str) -> NetworkBuilder {
+        self.inputs.push(name.to_string());
+        self
+    }
+
+    pub fn output(mut self, name: &str) -> NetworkBuilder {
+        self.outputs.push(name.to_string());
+        self
+    }
+
+    pub fn hidden(mut self, name: &str) -> NetworkBuilder {
+        self.hidden.push(name.to_string());
+        self
+    }
+
+    pub fn connect(self, src: &str, dest: &str, weight: Float) -> NetworkBuilder {
+        self.add_connection(src, dest, None, weight, false)
+    }
+
+    /// Adds a connection that is present in the genome but disabled
+    pub fn connect_disabled(self, src: &str, dest: &str, weight: Float) -> NetworkBuilder {
+        self.add_connection(src, dest, None, weight, true)
+    }
+
+    /// Adds a connection to a gate of a memory cell
+    pub fn connect_gate(self, src: &str, dest: &str, gate: Gate, weight: Float) -> NetworkBuilder {
+        self.add_connection(src, dest, Some(gate), weight, false)
+    }
+
+    /// Adds a connection to a gate of a memory cell that is present in the genome but disabled
+    pub fn connect_gate_disabled(self, src: &str, dest: &str, gate: Gate, weight: Float) -> NetworkBuilder {
+        self.add_connection(src, dest, Some(gate), weight, true)
+    }
+
+    pub fn activation(mut self, name: &str, activation: Activation) -> NetworkBuilder {
+        self.settings.push((name.to_string(), Setting::Activation(activation)));
+        self
+    }
+
+    pub fn aggregation(mut self, name: &str, aggregation: Aggregation) -> NetworkBuilder {
+        self.settings.push((name.to_string(), Setting::Aggregation(aggregation)));
+        self
+    }
+
+    pub fn bias(mut self, name: &str, bias: Float) -> NetworkBuilder {
+        self.settings.push((name.to_string(), Setting::Bias(bias)));
+        self
+    }
+
+    /// Attaches a memory cell to the node
+    pub fn memory(mut self, name: &str) -> NetworkBuilder {
+        self.settings.push((name.to_string(), Setting::Memory));
+        self
+    }
+
+    fn add_connection(mut self, src: &str, dest: &str, gate: Option<Gate>, weight: Float, disabled: bool) -> NetworkBuilder {
+        self.connections.push(Connection {
+            src: src.to_string(),
+            dest: dest.to_string(),
+            gate: gate,
+            weight: weight,
+            disabled: disabled
+        });
+        self
+    }
+
+    /// Returns the NID a node will get in the resulting network
+    pub fn nid(&self, name: &str) -> Option<NID> {
+        self.inputs.iter()
+            .chain(self.outputs.iter())
+            .chain(self.hidden.iter())
+            .position(|node| node == name)
+    }
+
+    fn resolve(&self, name: &str) -> Result<NID, BuildError> {
+        self.nid(name).ok_or_else(|| BuildError::UnknownNode(name.to_string()))
+    }
+
+    /// Validates the description and creates the network from it
+    pub fn build(self) -> Result<Network, BuildError> {
+        if self.outputs.is_empty() {
+            return Err(BuildError::NoOutputs);
+        }
+
+        let names = self.inputs.iter().chain(self.outputs.iter()).chain(self.hidden.iter()).collect::<Vec<_>>();
+        for (i, name) in names.iter().enumerate() {
+            if names[..i].contains(name) {
+                return Err(BuildError::DuplicateName(name.to_string()));
+            }
+        }
+
+        let mut nodes = Node::multiple_new(names.len());
+
+        for &(ref name, ref setting) in self.settings.iter() {
+            let node = &mut nodes[self.resolve(name)?];
+            match *setting {
+                Setting::Activation(activation) => node.activation = activation,
+                Setting::Aggregation(aggregation) => node.aggregation = aggregation,
+                Setting::Bias(bias) => node.bias = bias,
+                Setting::Memory => if !node.is_memory() { node.memory = Some(MemoryCell::new()) }
+            }
+        }
+
+        let mut genome: Vec<Gene> = Vec::new();
+        for connection in self.connections.iter() {
+            let src = self.resolve(&connection.src)?;
+            let dest = self.resolve(&connection.dest)?;
+            let gene = match connection.gate {
+                Some(gate) => {
+                    if !nodes[dest].is_memory() {
+                        return Err(BuildError::NotAMemoryCell(connection.dest.clone()));
+                    }
+                    Gene {
+                        disabled: connection.disabled,
+                        ..Gene::gate_with_weight(src, dest, gate, connection.weight)
+                    }
+                },
+                None => Gene::with_weight(src, dest, connection.disabled, connection.weight)
+            };
+            if genome.contains(&gene) {
+                return Err(BuildError::DuplicateConnection(connection.src.clone(), connection.dest.clone()));
+            }
+            genome.push(gene);
+        }
+
+        Ok(Network {
+            genome: genome,
+            nodes: nodes,
+            inputs: self.inputs.len(),
+            outputs: (self.inputs.len()..self.inputs.len() + self.outputs.len()).collect()
+        })
+    }
+}
+
+#[test]
+fn xor() {
+    let mut net = NetworkBuilder::new()
+        .input("x0").input("x1")
+        .output("y")
+        .hidden("or").hidden("and")
+        .connect("x0", "or", 1.0).connect("x1", "or", 1.0)
+        .connect("x0", "and", 1.0).connect("x1", "and", 1.0).bias("and", -1.0)
+        .connect("or", "y", 1.0).connect("and", "y", -2.0)
+        .build().unwrap();
+
+    assert_eq!(net.inputs, 2);
+    assert_eq!(net.outputs, vec![2]);
+    for &(x0, x1) in [(0.0, 0.0), (0.0, 1.0), (1.0, 0.0), (1.0, 1.0)].iter() {
+        assert_eq!(net.evaluate(&vec![x0, x1]).unwrap(), vec![(x0 as u8 ^ x1 as u8) as Float]);
+    }
+}
+
+#[test]
+fn validation() {
+    let builder = || NetworkBuilder::new().input("x").output("y").hidden("h");
+    assert_eq!(NetworkBuilder::new().input("x").build(), Err(BuildError::NoOutputs));
+    assert_eq!(builder().hidden("x").build(), Err(BuildError::DuplicateName("x".to_string())));
+    assert_eq!(builder().connect("x", "z", 1.0).build(), Err(BuildError::UnknownNode("z".to_string())));
+    assert_eq!(builder().activation("z", Activation::Tanh).build(), Err(BuildError::UnknownNode("z".to_string())));
+    assert_eq!(builder().connect("x", "y", 1.0).connect("x", "y", 2.0).build(),
+               Err(BuildError::DuplicateConnection("x".to_string(), "y".to_string())));
+    assert_eq!(builder().connect_gate("x", "h", Gate::Input, 1.0).build(), Err(BuildError::NotAMemoryCell("h".to_string())));
+    assert!(builder().memory("h").connect_gate("x", "h", Gate::Input, 1.0).build().is_ok());
+}
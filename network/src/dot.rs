@@ -0,0 +1,105 @@
+use {
+    Network,
+    TraceStep,
+    Float
+};
+
+impl Network {
+    /// Renders the network in the graphviz DOT language
+    ///
+    /// Inputs are drawn as boxes, outputs as double circles and memory cells as octagons.
+    /// Disabled genes are drawn dashed.
+    pub fn to_dot(&self) -> String {
+        self.render_dot(None)
+    }
+
+    /// Renders the network like to_dot() does but colours every node by its output and every gene by its contribution
+    /// during the given trace step (red for positive, blue for negative values).
+    pub fn to_dot_traced(&self, step: &TraceStep) -> String {
+        self.render_dot(Some(step))
+    }
+
+    fn render_dot(&self, step: Option<&TraceStep>) -> String {
+        let max_output = step.map_or(0.0, |step| {
+            step.nodes.iter().fold(0.0, |acc: Float, node| acc.max(node.output.abs()))
+        });
+        let max_contribution = step.map_or(0.0, |step| {
+            step.genes.iter().fold(0.0, |acc: Float, gene| acc.max(gene.contribution.abs()))
+        });
+
+        let mut dot = String::from("digraph network {\n    rankdir=LR;\n");
+
+        for (nid, node) in self.nodes.iter().enumerate() {
+            let shape = if nid < self.inputs {
+                "box"
+            } else if self.outputs.contains(&nid) {
+                "doublecircle"
+            } else if node.is_memory() {
+                "octagon"
+            } else {
+                "circle"
+            };
+            let mut attributes = format!("shape={}", shape);
+            if let Some(trace) = step.and_then(|step| step.get_node(nid)) {
+                attributes.push_str(&format!(
+                    ", style=filled, fillcolor=\"{}\", tooltip=\"in: {}, out: {}\"",
+                    color(trace.output, max_output), trace.input, trace.output
+                ));
+            }
+            dot.push_str(&format!("    {} [{}];\n", nid, attributes));
+        }
+
+        for (gid, gene) in self.genome.iter().enumerate() {
+            let mut label = format!("{:.3}", gene.weight);
+            if let Some(gate) = gene.gate {
+                label.push_str(&format!(" ({})", gate.name()));
+            }
+            let mut attributes = format!("label=\"{}\"", label);
+            if gene.disabled {
+                attributes.push_str(", style=dashed");
+            }
+            if let Some(trace) = step.and_then(|step| step.get_gene(gid)) {
+                attributes.push_str(&format!(", color=\"{}\", penwidth=2", color(trace.contribution, max_contribution)));
+            }
+            dot.push_str(&format!("    {} -> {} [{}];\n", gene.link.0, gene.link.1, attributes));
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Maps a value to a colour between white (zero) and full red (max) or full blue (-max)
+fn color(value: Float, max: Float) -> String {
+    let intensity = if max > 0.0 { (value.abs() / max).min(1.0) } else { 0.0 };
+    let fade = (255.0 * (1.0 - intensity)) as u8;
+    if value < 0.0 {
+        format!("#{:02x}{:02x}ff", fade, fade)
+    } else {
+        format!("#ff{:02x}{:02x}", fade, fade)
+    }
+}
+
+#[test]
+fn traced_dot() {
+    use Trace;
+
+    let mut net = Network::new_empty(2, 1);
+    net.genome[1].disable();
+    let mut trace = Trace::new();
+    net.evaluate_traced(&vec![1.0, 1.0], &mut trace).unwrap();
+
+    let dot = net.to_dot_traced(&trace.steps[0]);
+    assert!(dot.starts_with("digraph network {"));
+    assert!(dot.contains("1 -> 2 [label=\"") && dot.contains("style=dashed"));
+    // Node 1 is not connected to the output anymore and thus never evaluated
+    assert_eq!(dot.matches("fillcolor").count(), 2);
+    assert_eq!(dot.matches("penwidth").count(), 1);
+
+    // Gates are labeled with the names of the text format
+    let net = ::NetworkBuilder::new()
+        .input("x").output("y").memory("y")
+        .connect("x", "y", 1.0).connect_gate("x", "y", ::Gate::Forget, 0.5)
+        .build().unwrap();
+    assert!(net.to_dot().contains("label=\"0.500 (forget)\""));
+}
@@ -0,0 +1,354 @@
+use std::fmt;
+
+use {
+    NID,
+    Float,
+    Network,
+    Activation,
+    Aggregation
+};
+
+#[derive(Debug, PartialEq)]
+pub enum ExpressionError {
+    /// The network contains a cycle, so the outputs depend on the previous evaluations
+    Recurrent,
+    /// The node is a memory cell whose output depends on its state
+    MemoryCell(NID)
+}
+
+/// Symbolic formula that calculates an output of a network from its inputs
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    Constant(Float),
+    /// Input with the given index
+    Input(usize),
+    Sum(Vec<Expression>),
+    Product(Vec<Expression>),
+    /// Aggregation other than sum or product
+    Aggregate(Aggregation, Vec<Expression>),
+    Apply(Activation, Box<Expression>)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Syntax {
+    Text,
+    Latex,
+    Rust,
+    C
+}
+
+impl Network {
+    /// Builds a simplified expression for every output in terms of the inputs
+    ///
+    /// Genes with an absolute weight below min_weight are left out. Recurrent networks (considering only the
+    /// genes that are not left out) and networks that rely on memory cells can't be expressed without state
+    /// and therefore result in an error.
+    pub fn to_expression(&self, min_weight: Float) -> Result<Vec<Expression>, ExpressionError> {
+        let mut path = Vec::new();
+        self.outputs.iter().map(|output| {
+            self.get_node_expression(*output, min_weight, &mut path).map(Expression::simplify)
+        }).collect()
+    }
+
+    fn get_node_expression(&self, nid: NID, min_weight: Float, path: &mut Vec<NID>) -> Result<Expression, ExpressionError> {
+        if path.contains(&nid) {
+            return Err(ExpressionError::Recurrent);
+        }
+        let node = &self.nodes[nid];
+        if node.is_memory() {
+            return Err(ExpressionError::MemoryCell(nid));
+        }
+        path.push(nid);
+
+        let mut inputs = Vec::new();
+        if nid < self.inputs {
+            inputs.push(Expression::Input(nid));
+        }
+        for gene in self.genome.iter().filter(|gene| gene.link.1 == nid && !gene.disabled && gene.gate.is_none()) {
+            if gene.weight.abs() >= min_weight {
+                let source = self.get_node_expression(gene.link.0, min_weight, path)?;
+                inputs.push(Expression::Product(vec![Expression::Constant(gene.weight), source]));
+            }
+        }
+        path.pop();
+
+        let aggregated = match node.aggregation {
+            Aggregation::Sum => Expression::Sum(inputs),
+            Aggregation::Product if inputs.is_empty() => Expression::Constant(0.0),
+            Aggregation::Product => Expression::Product(inputs),
+            other => Expression::Aggregate(other, inputs)
+        };
+        Ok(Expression::Apply(node.activation, Box::new(Expression::Sum(vec![aggregated, Expression::Constant(node.bias)]))))
+    }
+}
+
+impl Expression {
+    /// Folds constants, flattens nested sums and products and removes neutral elements
+    pub fn simplify(self) -> Expression {
+        match self {
+            Expression::Sum(terms) => {
+                let mut constant = 0.0;
+                let mut simplified = Vec::new();
+                for term in terms.into_iter().map(Expression::simplify) {
+                    match term {
+                        Expression::Constant(value) => constant += value,
+                        Expression::Sum(inner) => {
+                            for inner_term in inner {
+                                match inner_term {
+                                    Expression::Constant(value) => constant += value,
+                                    other => simplified.push(other)
+                                }
+                            }
+                        },
+                        other => simplified.push(other)
+                    }
+                }
+                if constant != 0.0 || simplified.is_empty() {
+                    simplified.push(Expression::Constant(constant));
+                }
+                if simplified.len() == 1 { simplified.pop().unwrap() } else { Expression::Sum(simplified) }
+            },
+            Expression::Product(factors) => {
+                let mut constant = 1.0;
+                let mut simplified = Vec::new();
+                for factor in factors.into_iter().map(Expression::simplify) {
+                    match factor {
+                        Expression::Constant(value) => constant *= value,
+                        Expression::Product(inner) => {
+                            for inner_factor in inner {
+                                match inner_factor {
+                                    Expression::Constant(value) => constant *= value,
+                                    other => simplified.push(other)
+                                }
+                            }
+                        },
+                        other => simplified.push(other)
+                    }
+                }
+                if constant == 0.0 { return Expression::Constant(0.0) }
+                if constant != 1.0 || simplified.is_empty() {
+                    simplified.insert(0, Expression::Constant(constant));
+                }
+                if simplified.len() == 1 { simplified.pop().unwrap() } else { Expression::Product(simplified) }
+            },
+            Expression::Aggregate(aggregation, values) => {
+                let values = values.into_iter().map(Expression::simplify).collect::<Vec<_>>();
+                let constants = values.iter().filter_map(|value| match *value {
+                    Expression::Constant(constant) => Some(constant),
+                    _ => None
+                }).collect::<Vec<_>>();
+                if constants.len() == values.len() {
+                    Expression::Constant(aggregation.aggregate(&constants))
+                } else if values.len() == 1 {
+                    values.into_iter().next().unwrap()
+                } else {
+                    Expression::Aggregate(aggregation, values)
+                }
+            },
+            Expression::Apply(activation, argument) => {
+                match (activation, argument.simplify()) {
+                    (Activation::Identity, argument) => argument,
+                    (activation, Expression::Constant(value)) => Expression::Constant(activation.apply(value)),
+                    (activation, argument) => Expression::Apply(activation, Box::new(argument))
+                }
+            },
+            other => other
+        }
+    }
+
+    /// Calculates the value of the expression for the given inputs
+    pub fn evaluate(&self, inputs: &[Float]) -> Float {
+        match *self {
+            Expression::Constant(value) => value,
+            Expression::Input(index) => inputs[index],
+            Expression::Sum(ref terms) => terms.iter().fold(0.0, |acc, term| acc + term.evaluate(inputs)),
+            Expression::Product(ref factors) => factors.iter().fold(1.0, |acc, factor| acc * factor.evaluate(inputs)),
+            Expression::Aggregate(aggregation, ref values) => {
+                aggregation.aggregate(&values.iter().map(|value| value.evaluate(inputs)).collect::<Vec<_>>())
+            },
+            Expression::Apply(activation, ref argument) => activation.apply(argument.evaluate(inputs))
+        }
+    }
+
+    /// Plain text like `relu(0.5*x0 - 1.2*x1 + 0.3)`
+    pub fn to_text(&self) -> String {
+        self.print(Syntax::Text)
+    }
+
+    pub fn to_latex(&self) -> String {
+        self.print(Syntax::Latex)
+    }
+
+    /// Rust expression that reads the inputs from a slice `x`. Median and max-abs aggregations of more than one
+    /// value are printed as calls to `median(&[..])` and `maxabs(&[..])` which the surrounding code has to provide.
+    pub fn to_rust(&self) -> String {
+        self.print(Syntax::Rust)
+    }
+
+    /// C expression that reads the inputs from an array `x` and uses math.h. Median and max-abs aggregations of more
+    /// than one value are printed as calls to `median(values, n)` and `maxabs(values, n)` which have to be provided.
+    pub fn to_c(&self) -> String {
+        self.print(Syntax::C)
+    }
+
+    /// Returns the positive counterpart if the expression is a negative constant or starts with a negative factor
+    fn negated(&self) -> Option<Expression> {
+        match *self {
+            Expression::Constant(value) if value < 0.0 => Some(Expression::Constant(-value)),
+            Expression::Product(ref factors) => match factors.first() {
+                Some(&Expression::Constant(value)) if value < 0.0 => {
+                    let mut factors = factors.clone();
+                    factors[0] = Expression::Constant(-value);
+                    Some(Expression::Product(factors).simplify())
+                },
+                _ => None
+            },
+            _ => None
+        }
+    }
+
+    fn print(&self, syntax: Syntax) -> String {
+        match *self {
+            Expression::Constant(value) => match syntax {
+                Syntax::Text | Syntax::Latex => format!("{}", value),
+                Syntax::Rust | Syntax::C => format!("{:?}", value)
+            },
+            Expression::Input(index) => match syntax {
+                Syntax::Text => format!("x{}", index),
+                Syntax::Latex => format!("x_{{{}}}", index),
+                Syntax::Rust | Syntax::C => format!("x[{}]", index)
+            },
+            Expression::Sum(ref terms) => {
+                terms.iter().enumerate().fold(String::new(), |mut text, (i, term)| {
+                    match (i, term.negated()) {
+                        (0, _) => text.push_str(&term.print(syntax)),
+                        (_, Some(positive)) => text.push_str(&format!(" - {}", positive.print(syntax))),
+                        (_, None) => text.push_str(&format!(" + {}", term.print(syntax)))
+                    }
+                    text
+                })
+            },
+            Expression::Product(ref factors) => {
+                let separator = match syntax {
+                    Syntax::Text => "*",
+                    Syntax::Latex => " \\cdot ",
+                    Syntax::Rust | Syntax::C => " * "
+                };
+                factors.iter().map(|factor| match *factor {
+                    Expression::Sum(_) => wrap(&factor.print(syntax), syntax),
+                    _ => factor.print(syntax)
+                }).collect::<Vec<_>>().join(separator)
+            },
+            Expression::Aggregate(aggregation, ref values) => print_aggregate(aggregation, values, syntax),
+            Expression::Apply(activation, ref argument) => {
+                let argument = argument.print(syntax);
+                match syntax {
+                    Syntax::Text => format!("{}({})", activation.name(), argument),
+                    Syntax::Latex => match activation {
+                        Activation::Relu => format!("\\max\\left(0, {}\\right)", argument),
+                        Activation::Sigmoid => format!("\\frac{{1}}{{1 + e^{{-4.9 \\left({}\\right)}}}}", argument),
+                        Activation::Tanh => format!("\\tanh\\left({}\\right)", argument),
+                        Activation::Identity => argument,
+                        Activation::Gaussian => format!("e^{{-\\left({}\\right)^{{2}}}}", argument),
+                        Activation::Sin => format!("\\sin\\left({}\\right)", argument),
+                        Activation::Abs => format!("\\left|{}\\right|", argument)
+                    },
+                    Syntax::Rust => match activation {
+                        Activation::Relu => format!("({}).max(0.0)", argument),
+                        Activation::Sigmoid => format!("1.0 / (1.0 + (-4.9 * ({})).exp())", argument),
+                        Activation::Identity => argument,
+                        Activation::Gaussian => format!("(-({}).powi(2)).exp()", argument),
+                        other => format!("({}).{}()", argument, other.name())
+                    },
+                    Syntax::C => match activation {
+                        Activation::Relu => format!("fmax({}, 0.0)", argument),
+                        Activation::Sigmoid => format!("1.0 / (1.0 + exp(-4.9 * ({})))", argument),
+                        Activation::Identity => argument,
+                        Activation::Gaussian => format!("exp(-pow({}, 2.0))", argument),
+                        Activation::Abs => format!("fabs({})", argument),
+                        other => format!("{}({})", other.name(), argument)
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn wrap(text: &str, syntax: Syntax) -> String {
+    match syntax {
+        Syntax::Latex => format!("\\left({}\\right)", text),
+        _ => format!("({})", text)
+    }
+}
+
+fn print_aggregate(aggregation: Aggregation, values: &Vec<Expression>, syntax: Syntax) -> String {
+    let printed = values.iter().map(|value| value.print(syntax)).collect::<Vec<_>>();
+    let count = values.len() as Float;
+    match (syntax, aggregation) {
+        (Syntax::Text, _) => format!("{}({})", aggregation.name(), printed.join(", ")),
+        (Syntax::Latex, Aggregation::Mean) => format!("\\frac{{{}}}{{{}}}", printed.join(" + "), count),
+        (Syntax::Latex, Aggregation::Max) | (Syntax::Latex, Aggregation::Min) => {
+            format!("\\{}\\left({}\\right)", aggregation.name(), printed.join(", "))
+        },
+        (Syntax::Latex, _) => format!("\\operatorname{{{}}}\\left({}\\right)", aggregation.name(), printed.join(", ")),
+        (_, Aggregation::Mean) => {
+            format!("({}) / {:?}", printed.iter().map(|value| format!("({})", value)).collect::<Vec<_>>().join(" + "), count)
+        },
+        (Syntax::Rust, Aggregation::Max) | (Syntax::Rust, Aggregation::Min) => {
+            printed.iter().skip(1).fold(format!("({})", printed[0]), |acc, value| format!("{}.{}({})", acc, aggregation.name(), value))
+        },
+        (Syntax::C, Aggregation::Max) | (Syntax::C, Aggregation::Min) => {
+            printed.iter().skip(1).fold(printed[0].clone(), |acc, value| format!("f{}({}, {})", aggregation.name(), acc, value))
+        },
+        (Syntax::Rust, _) => format!("{}(&[{}])", aggregation.name(), printed.join(", ")),
+        (Syntax::C, _) => format!("{}((double[]){{{}}}, {})", aggregation.name(), printed.join(", "), values.len())
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_text())
+    }
+}
+
+#[cfg(test)]
+fn xor_network() -> Network {
+    use NetworkBuilder;
+
+    NetworkBuilder::new()
+        .input("x0").input("x1").activation("x0", Activation::Identity).activation("x1", Activation::Identity)
+        .output("y").activation("y", Activation::Identity)
+        .hidden("or").hidden("and")
+        .connect("x0", "or", 1.0).connect("x1", "or", 1.0)
+        .connect("x0", "and", 1.0).connect("x1", "and", 1.0).bias("and", -1.0)
+        .connect("or", "y", 1.0).connect("and", "y", -2.0).connect("x0", "y", 1e-9)
+        .build().unwrap()
+}
+
+#[test]
+fn xor_expression() {
+    let mut net = xor_network();
+    let expressions = net.to_expression(1e-6).unwrap();
+    assert_eq!(expressions[0].to_text(), "relu(x0 + x1) - 2*relu(x0 + x1 - 1)");
+    assert_eq!(expressions[0].to_rust(), "(x[0] + x[1]).max(0.0) - 2.0 * (x[0] + x[1] - 1.0).max(0.0)");
+    assert_eq!(expressions[0].to_c(), "fmax(x[0] + x[1], 0.0) - 2.0 * fmax(x[0] + x[1] - 1.0, 0.0)");
+    assert_eq!(expressions[0].to_latex(), "\\max\\left(0, x_{0} + x_{1}\\right) - 2 \\cdot \\max\\left(0, x_{0} + x_{1} - 1\\right)");
+    for inputs in [[0.0, 0.0], [0.0, 1.0], [1.0, 0.0], [1.0, 1.0], [0.3, -0.7]].iter() {
+        assert!((expressions[0].evaluate(inputs) - net.evaluate(&inputs.to_vec()).unwrap()[0]).abs() < 1e-6);
+    }
+}
+
+#[test]
+fn constant_folding() {
+    use Gene;
+
+    let mut net = Network::new_empty(1, 1);
+    net.genome[0].weight = 0.0;
+    net.nodes[1].bias = 2.0;
+    net.nodes[1].activation = Activation::Tanh;
+    assert_eq!(net.to_expression(0.0).unwrap(), vec![Expression::Constant((2.0 as Float).tanh())]);
+
+    net.genome.push(Gene::with_weight(1, 1, false, 0.5));
+    assert_eq!(net.to_expression(0.0), Err(ExpressionError::Recurrent));
+    assert!(net.to_expression(0.6).is_ok());
+}
@@ -0,0 +1,234 @@
+// C ABI for loading and evaluating networks (see include/neatwork.h)
+//
+// Networks are handed out as opaque pointers that have to be released with neat_network_free(). Every function
+// returns a NeatStatus and catches panics, so no unwinding ever crosses the FFI boundary.
+
+use std::panic::{self, AssertUnwindSafe};
+use std::slice;
+use std::str;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use {
+    Float,
+    Network
+};
+
+/// Opaque handle of a network
+pub struct NeatNetwork {
+    network: Network,
+    /// Identifies the loaded network, snapshots share it with the handle they were taken from
+    origin: usize
+}
+
+static NEXT_ORIGIN: AtomicUsize = AtomicUsize::new(0);
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NeatStatus {
+    Ok = 0,
+    NullPointer = 1,
+    InvalidUtf8 = 2,
+    ParseError = 3,
+    SizeMismatch = 4,
+    EvaluationError = 5,
+    Panic = 6,
+    StateMismatch = 7
+}
+
+/// Runs the body and turns a panic into NeatStatus::Panic
+fn guard<F>(body: F) -> NeatStatus where F: FnOnce() -> Result<(), NeatStatus> {
+    match panic::catch_unwind(AssertUnwindSafe(body)) {
+        Ok(Ok(())) => NeatStatus::Ok,
+        Ok(Err(status)) => status,
+        Err(_) => NeatStatus::Panic
+    }
+}
+
+unsafe fn get_ref<'a, T>(pointer: *const T) -> Result<&'a T, NeatStatus> {
+    pointer.as_ref().ok_or(NeatStatus::NullPointer)
+}
+
+unsafe fn get_mut<'a, T>(pointer: *mut T) -> Result<&'a mut T, NeatStatus> {
+    pointer.as_mut().ok_or(NeatStatus::NullPointer)
+}
+
+/// Static, null terminated description of a status
+///
+/// The status is taken as a plain integer, as C may pass values that aren't a variant of NeatStatus.
+#[no_mangle]
+pub extern "C" fn neat_status_message(status: u32) -> *const u8 {
+    let message: &'static [u8] = match status {
+        0 => b"ok\0",
+        1 => b"null pointer\0",
+        2 => b"invalid UTF-8\0",
+        3 => b"invalid network JSON\0",
+        4 => b"buffer size does not match the network\0",
+        5 => b"evaluation failed\0",
+        6 => b"internal error\0",
+        7 => b"snapshot was taken from a different network\0",
+        _ => b"unknown status\0"
+    };
+    message.as_ptr()
+}
+
+/// Parses a network from a JSON buffer (as written by Network::export()) and stores the new handle in out
+#[no_mangle]
+pub unsafe extern "C" fn neat_network_load_json(data: *const u8, length: usize, out: *mut *mut NeatNetwork) -> NeatStatus {
+    guard(|| {
+        if data.is_null() || out.is_null() {
+            return Err(NeatStatus::NullPointer);
+        }
+        let json = str::from_utf8(slice::from_raw_parts(data, length)).map_err(|_| NeatStatus::InvalidUtf8)?;
+        let network = Network::import(json.to_string()).map_err(|_| NeatStatus::ParseError)?;
+        *out = Box::into_raw(Box::new(NeatNetwork { network: network, origin: NEXT_ORIGIN.fetch_add(1, Ordering::Relaxed) }));
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn neat_network_input_count(network: *const NeatNetwork, out: *mut usize) -> NeatStatus {
+    guard(|| {
+        *get_mut(out)? = get_ref(network)?.network.inputs;
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn neat_network_output_count(network: *const NeatNetwork, out: *mut usize) -> NeatStatus {
+    guard(|| {
+        *get_mut(out)? = get_ref(network)?.network.outputs.len();
+        Ok(())
+    })
+}
+
+/// Evaluates the network once, the buffer lengths have to match the input and output counts
+#[no_mangle]
+pub unsafe extern "C" fn neat_network_evaluate(network: *mut NeatNetwork, inputs: *const Float, input_length: usize,
+                                               outputs: *mut Float, output_length: usize) -> NeatStatus {
+    guard(|| {
+        let network = &mut get_mut(network)?.network;
+        if inputs.is_null() || outputs.is_null() {
+            return Err(NeatStatus::NullPointer);
+        }
+        if input_length != network.inputs || output_length != network.outputs.len() {
+            return Err(NeatStatus::SizeMismatch);
+        }
+        let results = network.evaluate(&slice::from_raw_parts(inputs, input_length).to_vec()).map_err(|_| NeatStatus::EvaluationError)?;
+        slice::from_raw_parts_mut(outputs, output_length).copy_from_slice(&results);
+        Ok(())
+    })
+}
+
+/// Removes all recurrent data of the network
+#[no_mangle]
+pub unsafe extern "C" fn neat_network_reset(network: *mut NeatNetwork) -> NeatStatus {
+    guard(|| {
+        get_mut(network)?.network.reset();
+        Ok(())
+    })
+}
+
+/// Copies the network including its recurrent state into a new handle
+#[no_mangle]
+pub unsafe extern "C" fn neat_network_snapshot(network: *const NeatNetwork, out: *mut *mut NeatNetwork) -> NeatStatus {
+    guard(|| {
+        let network = get_ref(network)?;
+        let copy = NeatNetwork { network: network.network.clone(), origin: network.origin };
+        *get_mut(out)? = Box::into_raw(Box::new(copy));
+        Ok(())
+    })
+}
+
+/// Replaces the recurrent state (node outputs and memory cell contents) of the network with the one of a snapshot taken from it
+///
+/// Snapshots of any other network are rejected, even if it was loaded from the same JSON.
+#[no_mangle]
+pub unsafe extern "C" fn neat_network_restore(network: *mut NeatNetwork, snapshot: *const NeatNetwork) -> NeatStatus {
+    guard(|| {
+        let snapshot = get_ref(snapshot)?;
+        let network = get_mut(network)?;
+        // Networks loaded separately may have the same shape but still mean something completely different
+        if network.origin != snapshot.origin {
+            return Err(NeatStatus::StateMismatch);
+        }
+        let (network, snapshot) = (&mut network.network, &snapshot.network);
+        for (node, saved) in network.nodes.iter_mut().zip(snapshot.nodes.iter()) {
+            node.output = saved.output;
+            if let (Some(ref mut cell), Some(ref saved_cell)) = (node.memory.as_mut(), saved.memory.as_ref()) {
+                cell.state = saved_cell.state;
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Releases a network handle (null is ignored)
+#[no_mangle]
+pub unsafe extern "C" fn neat_network_free(network: *mut NeatNetwork) {
+    if !network.is_null() {
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| drop(Box::from_raw(network))));
+    }
+}
+
+#[test]
+fn ffi_round_trip() {
+    use std::ptr;
+    use {NetworkBuilder, Activation};
+
+    let json = NetworkBuilder::new()
+        .input("x").output("y").activation("x", Activation::Identity).activation("y", Activation::Identity)
+        .connect("x", "y", 2.0).connect("y", "y", 1.0)
+        .build().unwrap().export().unwrap();
+
+    unsafe {
+        let mut network = ptr::null_mut();
+        assert_eq!(neat_network_load_json(json.as_ptr(), json.len(), &mut network), NeatStatus::Ok);
+        let (mut inputs, mut outputs) = (0, 0);
+        assert_eq!(neat_network_input_count(network, &mut inputs), NeatStatus::Ok);
+        assert_eq!(neat_network_output_count(network, &mut outputs), NeatStatus::Ok);
+        assert_eq!((inputs, outputs), (1, 1));
+
+        let mut result = [0.0];
+        assert_eq!(neat_network_evaluate(network, [1.0].as_ptr(), 1, result.as_mut_ptr(), 1), NeatStatus::Ok);
+        assert_eq!(result, [2.0]);
+
+        let mut snapshot = ptr::null_mut();
+        assert_eq!(neat_network_snapshot(network, &mut snapshot), NeatStatus::Ok);
+        neat_network_evaluate(network, [1.0].as_ptr(), 1, result.as_mut_ptr(), 1);
+        assert_eq!(result, [4.0]);
+        assert_eq!(neat_network_restore(network, snapshot), NeatStatus::Ok);
+        neat_network_evaluate(network, [1.0].as_ptr(), 1, result.as_mut_ptr(), 1);
+        assert_eq!(result, [4.0]);
+        assert_eq!(neat_network_reset(network), NeatStatus::Ok);
+        neat_network_evaluate(network, [1.0].as_ptr(), 1, result.as_mut_ptr(), 1);
+        assert_eq!(result, [2.0]);
+
+        assert_eq!(neat_network_evaluate(network, [1.0].as_ptr(), 2, result.as_mut_ptr(), 1), NeatStatus::SizeMismatch);
+        assert_eq!(neat_network_evaluate(ptr::null_mut(), [1.0].as_ptr(), 1, result.as_mut_ptr(), 1), NeatStatus::NullPointer);
+
+        // A network with the same shape that was loaded on its own
+        let mut other = ptr::null_mut();
+        assert_eq!(neat_network_load_json(json.as_ptr(), json.len(), &mut other), NeatStatus::Ok);
+        assert_eq!(neat_network_restore(network, other), NeatStatus::StateMismatch);
+        assert_eq!(neat_network_restore(other, snapshot), NeatStatus::StateMismatch);
+        neat_network_free(other);
+
+        assert_eq!(neat_network_load_json(b"{".as_ptr(), 1, &mut snapshot), NeatStatus::ParseError);
+        neat_network_free(snapshot);
+        neat_network_free(network);
+    }
+
+    let message = |status: u32| unsafe { ::std::ffi::CStr::from_ptr(neat_status_message(status) as *const _) }.to_str().unwrap();
+    assert_eq!(message(NeatStatus::StateMismatch as u32), "snapshot was taken from a different network");
+    assert_eq!(message(42), "unknown status");
+}
+
+#[test]
+fn header_declares_all_functions() {
+    let header = include_str!("../include/neatwork.h");
+    let source = include_str!("ffi.rs");
+    for line in source.lines().filter(|line| line.starts_with("pub ") && line.contains("extern \"C\" fn ")) {
+        let name = line.split("fn ").nth(1).unwrap().split('(').next().unwrap();
+        assert!(header.contains(&format!("{}(", name)), "{} is missing in the header", name);
+    }
+}
@@ -0,0 +1,279 @@
+// Inference-only form of a network that works without std (and even without alloc)
+//
+// A frozen network is a set of slices that list the nodes in evaluation order together with the genes feeding into
+// them. Everything that changes during an evaluation lives in a separate FrozenState, so the network itself can be
+// stored in read-only memory (e.g. as statics that were generated from the output of Network::freeze()).
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use {
+    Float,
+    Activation,
+    Aggregation,
+    Gate,
+    EvaluationError
+};
+use memory::step_cell;
+#[cfg(feature = "std")]
+use {Network, plan::EvaluationPlan};
+
+/// Connection that feeds the output of a node into the node it belongs to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrozenGene {
+    /// Index of the source node output in the state
+    pub source: usize,
+    pub weight: Float,
+    /// Gate of the target memory cell (None for the regular node input)
+    pub gate: Option<Gate>
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrozenNode {
+    /// Index of the node output in the state
+    pub output: usize,
+    /// Network input that is fed into the node (only for input nodes)
+    pub input: Option<usize>,
+    /// Index of the first gene in the gene slice that belongs to this node
+    pub first_gene: usize,
+    pub gene_count: usize,
+    pub aggregation: Aggregation,
+    pub activation: Activation,
+    pub bias: Float,
+    /// Index of the memory cell state (only for memory nodes)
+    pub memory: Option<usize>
+}
+
+/// Read-only description of a network
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrozenNetwork<'a> {
+    pub inputs: usize,
+    /// Nodes in the order they are evaluated
+    pub nodes: &'a [FrozenNode],
+    pub genes: &'a [FrozenGene],
+    /// Indices of the output node outputs in the state
+    pub outputs: &'a [usize],
+    /// Amount of node outputs the state has to store
+    pub state_size: usize,
+    /// Amount of memory cells the state has to store
+    pub memory_cells: usize,
+    /// Amount of node inputs that have to be aggregated at once
+    pub scratch_size: usize
+}
+
+/// Buffers that hold the recurrent data of a frozen network and the space needed to aggregate the node inputs
+#[derive(Debug)]
+pub struct FrozenState<'a> {
+    pub outputs: &'a mut [Float],
+    pub cells: &'a mut [Float],
+    pub scratch: &'a mut [Float]
+}
+
+impl<'a> FrozenState<'a> {
+    pub fn new(outputs: &'a mut [Float], cells: &'a mut [Float], scratch: &'a mut [Float]) -> FrozenState<'a> {
+        FrozenState {
+            outputs: outputs,
+            cells: cells,
+            scratch: scratch
+        }
+    }
+
+    /// Removes all recurrent data
+    pub fn reset(&mut self) {
+        for value in self.outputs.iter_mut().chain(self.cells.iter_mut()) {
+            *value = 0.0;
+        }
+    }
+}
+
+impl<'a> FrozenNetwork<'a> {
+    /// Evaluates the network exactly like Network::evaluate() and writes the results into outputs
+    pub fn evaluate(&self, state: &mut FrozenState, inputs: &[Float], outputs: &mut [Float]) -> Result<(), EvaluationError> {
+        if inputs.len() != self.inputs {
+            return Err(EvaluationError::InputSizeMismatch);
+        }
+        if outputs.len() != self.outputs.len() || state.outputs.len() < self.state_size
+            || state.cells.len() < self.memory_cells || state.scratch.len() < self.scratch_size {
+            return Err(EvaluationError::BufferSizeMismatch);
+        }
+
+        for node in self.nodes.iter() {
+            let mut count = 0;
+            let mut gates = [0.0; 3];
+            if let Some(input) = node.input {
+                state.scratch[count] = inputs[input];
+                count += 1;
+            }
+            for gene in self.genes[node.first_gene..node.first_gene + node.gene_count].iter() {
+                let contribution = state.outputs[gene.source] * gene.weight;
+                match gene.gate {
+                    None => {
+                        state.scratch[count] = contribution;
+                        count += 1;
+                    },
+                    Some(gate) => gates[gate as usize] += contribution
+                }
+            }
+
+            let input = node.aggregation.aggregate_in_place(&mut state.scratch[..count]) + node.bias;
+            state.outputs[node.output] = match node.memory {
+                Some(cell) => step_cell(&mut state.cells[cell], gates[Gate::Input as usize], gates[Gate::Forget as usize], gates[Gate::Output as usize], input),
+                None => node.activation.apply(input)
+            };
+        }
+
+        for (value, output) in outputs.iter_mut().zip(self.outputs.iter()) {
+            *value = state.outputs[*output];
+        }
+        Ok(())
+    }
+}
+
+/// Frozen network that owns its description and state
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct OwnedFrozenNetwork {
+    inputs: usize,
+    nodes: Vec<FrozenNode>,
+    genes: Vec<FrozenGene>,
+    outputs: Vec<usize>,
+    scratch_size: usize,
+    state: Vec<Float>,
+    cells: Vec<Float>,
+    scratch: Vec<Float>
+}
+
+#[cfg(feature = "alloc")]
+impl OwnedFrozenNetwork {
+    /// Read-only view of the network (which can be printed to embed it into a build without alloc)
+    pub fn get_network(&self) -> FrozenNetwork<'_> {
+        FrozenNetwork {
+            inputs: self.inputs,
+            nodes: &self.nodes,
+            genes: &self.genes,
+            outputs: &self.outputs,
+            state_size: self.state.len(),
+            memory_cells: self.cells.len(),
+            scratch_size: self.scratch_size
+        }
+    }
+
+    /// Read-only view of the network together with its state
+    pub fn split(&mut self) -> (FrozenNetwork<'_>, FrozenState<'_>) {
+        let network = FrozenNetwork {
+            inputs: self.inputs,
+            nodes: &self.nodes,
+            genes: &self.genes,
+            outputs: &self.outputs,
+            state_size: self.state.len(),
+            memory_cells: self.cells.len(),
+            scratch_size: self.scratch_size
+        };
+        (network, FrozenState::new(&mut self.state, &mut self.cells, &mut self.scratch))
+    }
+
+    pub fn evaluate(&mut self, inputs: &[Float]) -> Result<Vec<Float>, EvaluationError> {
+        let mut outputs = vec![0.0; self.outputs.len()];
+        let (network, mut state) = self.split();
+        network.evaluate(&mut state, inputs, &mut outputs)?;
+        Ok(outputs)
+    }
+
+    /// Removes all recurrent data
+    pub fn reset(&mut self) {
+        FrozenState::new(&mut self.state, &mut self.cells, &mut self.scratch).reset();
+    }
+}
+
+#[cfg(feature = "std")]
+impl Network {
+    /// Creates an inference-only copy of the network that continues from its current state
+    pub fn freeze(&self) -> OwnedFrozenNetwork {
+        let plan = EvaluationPlan::new(self);
+        let mut nodes = Vec::new();
+        let mut genes = Vec::new();
+        let mut cells = Vec::new();
+        let mut scratch_size = 0;
+
+        for step in plan.steps.iter() {
+            let node = &self.nodes[step.node];
+            let first_gene = genes.len();
+            for gene in step.genes.iter().map(|gene| &self.genome[*gene]) {
+                // Gates of nodes without a memory cell are ignored by the regular evaluation as well
+                if gene.gate.is_none() || node.is_memory() {
+                    genes.push(FrozenGene { source: gene.link.0, weight: gene.weight, gate: gene.gate });
+                }
+            }
+            let input = if step.node < self.inputs { Some(step.node) } else { None };
+            let direct_inputs = genes[first_gene..].iter().filter(|gene| gene.gate.is_none()).count() + input.iter().count();
+            scratch_size = scratch_size.max(direct_inputs);
+
+            nodes.push(FrozenNode {
+                output: step.node,
+                input: input,
+                first_gene: first_gene,
+                gene_count: genes.len() - first_gene,
+                aggregation: node.aggregation,
+                activation: node.activation,
+                bias: node.bias,
+                memory: node.memory.as_ref().map(|cell| {
+                    cells.push(cell.state);
+                    cells.len() - 1
+                })
+            });
+        }
+
+        OwnedFrozenNetwork {
+            inputs: self.inputs,
+            nodes: nodes,
+            genes: genes,
+            outputs: self.outputs.clone(),
+            scratch_size: scratch_size,
+            state: self.nodes.iter().map(|node| node.output).collect(),
+            cells: cells,
+            scratch: vec![0.0; scratch_size]
+        }
+    }
+}
+
+#[test]
+fn frozen_evaluation() {
+    use NetworkBuilder;
+
+    let mut net = NetworkBuilder::new()
+        .input("a").input("b").output("y").hidden("m").memory("m").hidden("h")
+        .activation("h", Activation::Tanh).aggregation("h", Aggregation::Median).bias("h", 0.2)
+        .connect("a", "m", 0.7).connect_gate("b", "m", Gate::Input, 2.0).connect_gate("a", "m", Gate::Forget, -1.0)
+        .connect("a", "h", 0.5).connect("b", "h", -1.5).connect("y", "h", 0.3)
+        .connect("m", "y", 1.2).connect("h", "y", 0.8).connect_gate("a", "m", Gate::Output, 5.0)
+        .build().unwrap();
+    net.evaluate(&vec![0.3, 0.1]).unwrap();
+
+    let mut frozen = net.freeze();
+    for i in 0..10 {
+        let inputs = vec![(i as Float * 0.7).sin(), (i as Float * 0.3).cos()];
+        assert_eq!(frozen.evaluate(&inputs).unwrap(), net.evaluate(&inputs).unwrap());
+    }
+    frozen.reset();
+    net.reset();
+    assert_eq!(frozen.evaluate(&[1.0, -1.0]).unwrap(), net.evaluate(&vec![1.0, -1.0]).unwrap());
+    assert!(frozen.evaluate(&[1.0]).is_err());
+}
+
+#[test]
+fn static_frozen_network() {
+    // y = relu(2 * x - 1) without any allocations
+    static NODES: [FrozenNode; 2] = [
+        FrozenNode { output: 0, input: Some(0), first_gene: 0, gene_count: 0, aggregation: Aggregation::Sum, activation: Activation::Identity, bias: 0.0, memory: None },
+        FrozenNode { output: 1, input: None, first_gene: 0, gene_count: 1, aggregation: Aggregation::Sum, activation: Activation::Relu, bias: -1.0, memory: None }
+    ];
+    static GENES: [FrozenGene; 1] = [FrozenGene { source: 0, weight: 2.0, gate: None }];
+    let network = FrozenNetwork { inputs: 1, nodes: &NODES, genes: &GENES, outputs: &[1], state_size: 2, memory_cells: 0, scratch_size: 1 };
+
+    let (mut outputs, mut cells, mut scratch) = ([0.0; 2], [0.0; 0], [0.0; 1]);
+    let mut state = FrozenState::new(&mut outputs, &mut cells, &mut scratch);
+    let mut result = [0.0];
+    network.evaluate(&mut state, &[1.5], &mut result).unwrap();
+    assert_eq!(result, [2.0]);
+    assert!(network.evaluate(&mut state, &[1.5], &mut []).is_err());
+}
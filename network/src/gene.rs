@@ -1,7 +1,9 @@
+#[cfg(feature = "training")]
 use rand::{thread_rng, Rng};
 use {
     NID,
     Float,
+    Gate,
     Link
 };
 
@@ -12,36 +14,54 @@ const GENE_MUT_RESET: Float = 0.1;
 const GENE_MUT_STRENGTH: Float = 0.1; //0.5; //100% = 1.0
 
 /// Struct that represents a gene which in turn represents a connection/link inside a network
-#[derive(Debug, RustcDecodable, RustcEncodable, Clone)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialization", derive(RustcDecodable, RustcEncodable))]
 pub struct Gene {
     /// Whether or not this gene has been disabled
     pub disabled: bool,
     /// The multiplier that is applied for data passing through this link
     pub weight: Float,
     /// Endpoints of the link
-    pub link: Link
+    pub link: Link,
+    /// Gate of the target memory cell this gene feeds into (None for the regular node input)
+    pub gate: Option<Gate>
 }
 
 impl Gene {
-    fn random_weight() -> Float {
-        thread_rng().gen::<Float>()*2.0 - 1.0
+    /// Uniformly distributed weight between -1 and 1
+    #[cfg(feature = "training")]
+    pub fn random_weight<R: Rng>(rng: &mut R) -> Float {
+        rng.gen::<Float>()*2.0 - 1.0
     }
 
+    #[cfg(feature = "training")]
     pub fn random(src: NID, dest: NID, disabled: bool) -> Gene {
+        Gene::random_with_rng(src, dest, disabled, &mut thread_rng())
+    }
+
+    /// Like random() but using the given generator
+    #[cfg(feature = "training")]
+    pub fn random_with_rng<R: Rng>(src: NID, dest: NID, disabled: bool, rng: &mut R) -> Gene {
         Gene {
             disabled: disabled,
-            weight: Gene::random_weight(),  // TODO: Improve this w/ a thread wide generator (aka species wide)
-            link: (src, dest)
+            weight: Gene::random_weight(rng),
+            link: (src, dest),
+            gate: None
         }
     }
 
+    #[cfg(feature = "training")]
     pub fn mutate(&mut self) {
-        if thread_rng().gen::<Float>() < GENE_MUT_RESET {
-            self.weight = Gene::random_weight();
+        self.mutate_with(&mut thread_rng(), GENE_MUT_RESET, GENE_MUT_STRENGTH);
+    }
+
+    /// Replaces the weight by a random one with the given probability and shifts it by up to +-strength otherwise
+    #[cfg(feature = "training")]
+    pub fn mutate_with<R: Rng>(&mut self, rng: &mut R, reset_probability: Float, strength: Float) {
+        if rng.gen::<Float>() < reset_probability {
+            self.weight = Gene::random_weight(rng);
         } else {
-            // println!("{} =>", self.weight);
-            self.weight += Gene::random_weight() * GENE_MUT_STRENGTH;
-            // println!("{}", self.weight);
+            self.weight += Gene::random_weight(rng) * strength;
         }
     }
 
@@ -49,12 +69,28 @@ impl Gene {
         Gene {
             disabled: disabled,
             weight: weight,
-            link: (src, dest)
+            link: (src, dest),
+            gate: None
+        }
+    }
+
+    /// Creates a gene that feeds into a gate of the target memory cell
+    pub fn gate_with_weight(src: NID, dest: NID, gate: Gate, weight: Float) -> Gene {
+        Gene {
+            gate: Some(gate),
+            ..Gene::with_weight(src, dest, false, weight)
         }
     }
 
+    #[cfg(feature = "training")]
     pub fn merge(&mut self, other: &Gene) {
-        if !other.disabled && thread_rng().gen::<Float>() > GENE_WEIGHT_MERGE_PROB {
+        self.merge_with(&mut thread_rng(), other, GENE_WEIGHT_MERGE_PROB);
+    }
+
+    /// Keeps the own weight with the given probability and takes the one of the other (enabled) gene otherwise
+    #[cfg(feature = "training")]
+    pub fn merge_with<R: Rng>(&mut self, rng: &mut R, other: &Gene, keep_probability: Float) {
+        if !other.disabled && rng.gen::<Float>() > keep_probability {
             self.weight = other.weight;
         }
     }
@@ -72,9 +108,14 @@ impl Gene {
     }
 }
 
+/// Genes are the same connection if they have the same link and feed the same gate
+///
+/// A node can feed both the input and the gates of a memory cell, so genes with the same link but different gates
+/// are separate connections: crossover only merges weights of genes that also agree on the gate and adding a
+/// connection to another gate adds a new gene instead of reenabling the existing one.
 impl PartialEq for Gene {
     fn eq(&self, other: &Gene) -> bool {
-        self.link == other.link
+        self.link == other.link && self.gate == other.gate
     }
 
     fn ne(&self, other: &Gene) -> bool {
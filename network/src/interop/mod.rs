@@ -0,0 +1,18 @@
+/// Import and export of neat-python genomes (config plus JSON dump of a DefaultGenome)
+#[cfg(feature = "serialization")]
+pub mod neat_python;
+/// Import of SharpNEAT XML genomes
+pub mod sharpneat;
+mod xml;
+
+#[derive(Debug, PartialEq)]
+pub enum InteropError {
+    /// The input is not well formed (description of the problem)
+    Malformed(String),
+    /// A field that is required for the conversion is missing
+    MissingField(String),
+    /// The genome uses something the target representation can't express
+    Unsupported(String),
+    /// A connection refers to a node that does not exist
+    UnknownNode(String)
+}
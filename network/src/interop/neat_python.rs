@@ -0,0 +1,340 @@
+// neat-python stores genomes as pickled Python objects. The importer and exporter therefore work on a JSON dump
+// of a DefaultGenome that can be created and loaded with a few lines of Python:
+//
+//     data = {"key": genome.key,
+//             "nodes": [{"key": n.key, "bias": n.bias, "response": n.response,
+//                        "activation": n.activation, "aggregation": n.aggregation} for n in genome.nodes.values()],
+//             "connections": [{"key": list(c.key), "weight": c.weight, "enabled": c.enabled}
+//                             for c in genome.connections.values()]}
+//
+//     genome = neat.DefaultGenome(data["key"])
+//     for n in data["nodes"]:
+//         node = neat.genes.DefaultNodeGene(n["key"])
+//         node.bias, node.response = n["bias"], n["response"]
+//         node.activation, node.aggregation = n["activation"], n["aggregation"]
+//         genome.nodes[node.key] = node
+//     for c in data["connections"]:
+//         conn = neat.genes.DefaultConnectionGene(tuple(c["key"]))
+//         conn.weight, conn.enabled = c["weight"], c["enabled"]
+//         genome.connections[conn.key] = conn
+//
+// neat-python computes `activation(bias + response * aggregation(inputs))` and scales the input of most
+// activation functions (e.g. sigmoid(z) = 1 / (1 + exp(-5z))). On import these factors are folded into the bias
+// and the weights of incoming genes, on export they are expressed through the response. neat-python additionally clamps
+// the scaled input of sin to [-60, 60], so imported sin nodes only match for inputs within [-12, 12].
+
+use std::collections::BTreeMap;
+use rustc_serialize::json::Json;
+
+use {
+    NID,
+    Float,
+    Network,
+    Node,
+    Gene,
+    Activation,
+    Aggregation
+};
+use super::InteropError;
+
+/// Maps a neat-python activation function to a local one and the factor neat-python scales its input with
+fn activation_from_name(name: &str) -> Result<(Activation, Float), InteropError> {
+    match name {
+        "sigmoid" => Ok((Activation::Sigmoid, 5.0 / 4.9)),
+        "tanh" => Ok((Activation::Tanh, 2.5)),
+        "gauss" => Ok((Activation::Gaussian, (5.0 as Float).sqrt())),
+        "relu" => Ok((Activation::Relu, 1.0)),
+        "identity" => Ok((Activation::Identity, 1.0)),
+        "abs" => Ok((Activation::Abs, 1.0)),
+        "sin" => Ok((Activation::Sin, 5.0)),
+        other => Err(InteropError::Unsupported(format!("activation function '{}'", other)))
+    }
+}
+
+fn activation_to_name(activation: Activation) -> Result<(&'static str, Float), InteropError> {
+    for name in ["sigmoid", "tanh", "gauss", "relu", "identity", "abs", "sin"].iter() {
+        let (candidate, scale) = activation_from_name(name)?;
+        if candidate == activation {
+            return Ok((name, scale));
+        }
+    }
+    Err(InteropError::Unsupported(format!("activation function '{}'", activation.name())))
+}
+
+fn read_config(config: &str) -> Result<(usize, usize), InteropError> {
+    let mut section = "";
+    let mut inputs = None;
+    let mut outputs = None;
+
+    for line in config.lines() {
+        let line = line.split(|c| c == '#' || c == ';').next().unwrap_or("").trim();
+        if line.starts_with('[') && line.ends_with(']') {
+            section = &line[1..line.len() - 1];
+        } else if section == "DefaultGenome" {
+            let mut parts = line.splitn(2, '=');
+            let key = parts.next().unwrap_or("").trim();
+            let value = parts.next().unwrap_or("").trim();
+            let target = match key {
+                "num_inputs" => &mut inputs,
+                "num_outputs" => &mut outputs,
+                _ => continue
+            };
+            *target = Some(value.parse::<usize>().map_err(|_| {
+                InteropError::Malformed(format!("invalid value '{}' for {}", value, key))
+            })?);
+        }
+    }
+
+    match (inputs, outputs) {
+        (Some(inputs), Some(outputs)) => Ok((inputs, outputs)),
+        (None, _) => Err(InteropError::MissingField("num_inputs".to_string())),
+        (_, None) => Err(InteropError::MissingField("num_outputs".to_string()))
+    }
+}
+
+fn get_field<'a>(object: &'a Json, field: &str) -> Result<&'a Json, InteropError> {
+    object.find(field).ok_or_else(|| InteropError::MissingField(field.to_string()))
+}
+
+fn get_number(object: &Json, field: &str) -> Result<Float, InteropError> {
+    get_field(object, field)?.as_f64().map(|value| value as Float).ok_or_else(|| {
+        InteropError::Malformed(format!("'{}' is not a number", field))
+    })
+}
+
+fn get_key(key: &Json) -> Result<i64, InteropError> {
+    key.as_i64().ok_or_else(|| InteropError::Malformed(format!("invalid node key {}", key)))
+}
+
+/// Converts a neat-python genome (JSON dump, see above) into a network using num_inputs and num_outputs of the config
+pub fn import(config: &str, genome: &str) -> Result<Network, InteropError> {
+    let (inputs, outputs) = read_config(config)?;
+    let genome = Json::from_str(genome).map_err(|error| InteropError::Malformed(format!("{}", error)))?;
+    let node_genes = get_field(&genome, "nodes")?.as_array().ok_or_else(|| InteropError::Malformed("'nodes' is not a list".to_string()))?;
+    let connection_genes = get_field(&genome, "connections")?.as_array().ok_or_else(|| InteropError::Malformed("'connections' is not a list".to_string()))?;
+
+    // Inputs are -1, -2, ..., outputs 0, 1, ... and every other key is a hidden node
+    let mut keys = (1..inputs as i64 + 1).map(|key| -key).chain(0..outputs as i64).collect::<Vec<_>>();
+    let mut hidden_keys = Vec::new();
+    for node_gene in node_genes.iter() {
+        let key = get_key(get_field(node_gene, "key")?)?;
+        if key < 0 {
+            return Err(InteropError::Unsupported(format!("node gene for input {}", key)));
+        } else if key >= outputs as i64 {
+            hidden_keys.push(key);
+        }
+    }
+    hidden_keys.sort();
+    keys.extend(hidden_keys);
+    let get_nid = |key: i64| -> Result<NID, InteropError> {
+        keys.iter().position(|other| *other == key).ok_or_else(|| InteropError::UnknownNode(key.to_string()))
+    };
+
+    let mut nodes = Node::multiple_new(keys.len());
+    for node in nodes[..inputs].iter_mut() {
+        node.activation = Activation::Identity;
+    }
+
+    // Factor the weights of genes targeting a node are scaled with
+    let mut scales = vec![1.0; keys.len()];
+    let mut defined = vec![false; keys.len()];
+    for node_gene in node_genes.iter() {
+        let nid = get_nid(get_key(get_field(node_gene, "key")?)?)?;
+        let (activation, activation_scale) = activation_from_name(get_field(node_gene, "activation")?.as_string().unwrap_or(""))?;
+        let aggregation_name = node_gene.find("aggregation").and_then(Json::as_string).unwrap_or("sum");
+        let aggregation = Aggregation::from_name(aggregation_name).ok_or_else(|| {
+            InteropError::Unsupported(format!("aggregation function '{}'", aggregation_name))
+        })?;
+        let response = match node_gene.find("response") {
+            Some(_) => get_number(node_gene, "response")?,
+            None => 1.0
+        };
+
+        let mut scale = activation_scale * response;
+        if (scale - 1.0).abs() < 1e-12 {
+            scale = 1.0;
+        }
+        if scale != 1.0 && aggregation == Aggregation::Product {
+            return Err(InteropError::Unsupported(format!("product aggregation with a scaled activation function or response in node {}", keys[nid])));
+        }
+        if scale < 0.0 && (aggregation == Aggregation::Max || aggregation == Aggregation::Min) {
+            return Err(InteropError::Unsupported(format!("negative response with {} aggregation in node {}", aggregation.name(), keys[nid])));
+        }
+
+        nodes[nid].activation = activation;
+        nodes[nid].aggregation = aggregation;
+        nodes[nid].bias = activation_scale * get_number(node_gene, "bias")?;
+        scales[nid] = scale;
+        defined[nid] = true;
+    }
+    if let Some(nid) = (inputs..keys.len()).find(|nid| !defined[*nid]) {
+        return Err(InteropError::MissingField(format!("node gene for {}", keys[nid])));
+    }
+
+    let mut genome = Vec::new();
+    for connection_gene in connection_genes.iter() {
+        let key = get_field(connection_gene, "key")?.as_array().map_or(Vec::new(), |key| key.clone());
+        if key.len() != 2 {
+            return Err(InteropError::Malformed("connection key is not a pair".to_string()));
+        }
+        let src = get_nid(get_key(&key[0])?)?;
+        let dest = get_nid(get_key(&key[1])?)?;
+        if dest < inputs {
+            return Err(InteropError::Unsupported(format!("connection into input {}", keys[dest])));
+        }
+        let enabled = match connection_gene.find("enabled") {
+            Some(enabled) => enabled.as_boolean().ok_or_else(|| InteropError::Malformed("'enabled' is not a boolean".to_string()))?,
+            None => true
+        };
+        let weight = get_number(connection_gene, "weight")?;
+        genome.push(Gene::with_weight(src, dest, !enabled, weight * scales[dest]));
+    }
+
+    Ok(Network {
+        genome: genome,
+        nodes: nodes,
+        inputs: inputs,
+        outputs: (inputs..inputs + outputs).collect()
+    })
+}
+
+/// Converts a network into a neat-python config and a genome (JSON dump, see above)
+///
+/// Input nodes that do more than passing on their value (activation function, bias or incoming genes)
+/// are represented by an additional hidden node, as neat-python inputs can't be configured.
+/// Memory cells can't be represented and cause an error.
+pub fn export(network: &Network) -> Result<(String, String), InteropError> {
+    if network.nodes.iter().any(Node::is_memory) || network.genome.iter().any(|gene| gene.gate.is_some()) {
+        return Err(InteropError::Unsupported("memory cells".to_string()));
+    }
+
+    let mut keys = (0..network.nodes.len()).map(|nid| {
+        if nid < network.inputs {
+            -(nid as i64) - 1
+        } else {
+            network.outputs.iter().position(|output| *output == nid).map_or(-1, |output_id| output_id as i64)
+        }
+    }).collect::<Vec<_>>();
+    let mut next_key = network.outputs.len() as i64;
+    for nid in network.inputs..network.nodes.len() {
+        if keys[nid] == -1 {
+            keys[nid] = next_key;
+            next_key += 1;
+        }
+    }
+
+    // Key of the node that represents the value of an input node for other nodes
+    let mut relay_keys = keys.clone();
+    let mut node_genes = Vec::new();
+    let mut connection_genes = Vec::new();
+    let mut activations = Vec::new();
+    let mut aggregations = Vec::new();
+
+    for (nid, node) in network.nodes.iter().enumerate() {
+        let is_input = nid < network.inputs;
+        let has_incoming_genes = network.genome.iter().any(|gene| gene.link.1 == nid);
+        if is_input && node.activation == Activation::Identity && node.bias == 0.0 && !has_incoming_genes {
+            continue;
+        }
+        if is_input {
+            relay_keys[nid] = next_key;
+            next_key += 1;
+            connection_genes.push(connection_to_json(keys[nid], relay_keys[nid], 1.0, true));
+        }
+
+        let (activation, scale) = activation_to_name(node.activation)?;
+        if !activations.contains(&activation) { activations.push(activation) }
+        if !aggregations.contains(&node.aggregation.name()) { aggregations.push(node.aggregation.name()) }
+
+        let mut node_gene = BTreeMap::new();
+        node_gene.insert("key".to_string(), Json::I64(relay_keys[nid]));
+        node_gene.insert("bias".to_string(), Json::F64((node.bias / scale) as f64));
+        node_gene.insert("response".to_string(), Json::F64((1.0 / scale) as f64));
+        node_gene.insert("activation".to_string(), Json::String(activation.to_string()));
+        node_gene.insert("aggregation".to_string(), Json::String(node.aggregation.name().to_string()));
+        node_genes.push(Json::Object(node_gene));
+    }
+
+    for gene in network.genome.iter() {
+        connection_genes.push(connection_to_json(relay_keys[gene.link.0], relay_keys[gene.link.1], gene.weight, !gene.disabled));
+    }
+
+    let mut genome = BTreeMap::new();
+    genome.insert("key".to_string(), Json::I64(0));
+    genome.insert("nodes".to_string(), Json::Array(node_genes));
+    genome.insert("connections".to_string(), Json::Array(connection_genes));
+
+    if activations.is_empty() { activations.push("identity") }
+    if aggregations.is_empty() { aggregations.push("sum") }
+    let config = format!(include_str!("neat_python_config.ini"),
+        inputs = network.inputs,
+        outputs = network.outputs.len(),
+        feed_forward = if network.is_recurrent() { "False" } else { "True" },
+        activation_default = activations[0],
+        activation_options = activations.join(" "),
+        aggregation_default = aggregations[0],
+        aggregation_options = aggregations.join(" ")
+    );
+
+    Ok((config, format!("{}", Json::Object(genome).pretty())))
+}
+
+fn connection_to_json(src: i64, dest: i64, weight: Float, enabled: bool) -> Json {
+    let mut connection_gene = BTreeMap::new();
+    connection_gene.insert("key".to_string(), Json::Array(vec![Json::I64(src), Json::I64(dest)]));
+    connection_gene.insert("weight".to_string(), Json::F64(weight as f64));
+    connection_gene.insert("enabled".to_string(), Json::Boolean(enabled));
+    Json::Object(connection_gene)
+}
+
+#[cfg(test)]
+const TEST_CONFIG: &'static str = "[NEAT]\npop_size = 10\n\n[DefaultGenome]\nnum_inputs = 2 # x and y\nnum_outputs = 1\n";
+
+#[test]
+fn import_genome() {
+    let genome = r#"{"key": 7, "nodes": [
+        {"key": 0, "bias": 0.0, "response": 1.0, "activation": "identity", "aggregation": "sum"},
+        {"key": 5, "bias": -1.0, "response": 1.0, "activation": "relu", "aggregation": "sum"}
+    ], "connections": [
+        {"key": [-1, 5], "weight": 1.0, "enabled": true},
+        {"key": [-2, 5], "weight": 1.0, "enabled": true},
+        {"key": [-1, 0], "weight": 0.5, "enabled": false},
+        {"key": [5, 0], "weight": -2.0, "enabled": true}
+    ]}"#;
+    let mut net = import(TEST_CONFIG, genome).unwrap();
+    assert_eq!(net.nodes.len(), 4);
+    assert!(net.genome[2].disabled);
+    assert_eq!(net.evaluate(&vec![-3.0, 1.0]).unwrap(), vec![0.0]);
+    assert_eq!(net.evaluate(&vec![1.0, 1.0]).unwrap(), vec![-2.0]);
+}
+
+#[test]
+fn unsupported_genome() {
+    let genome = r#"{"nodes": [{"key": 0, "bias": 0.0, "activation": "cube"}], "connections": []}"#;
+    assert_eq!(import(TEST_CONFIG, genome), Err(InteropError::Unsupported("activation function 'cube'".to_string())));
+    assert_eq!(import("[DefaultGenome]\nnum_outputs = 1", genome), Err(InteropError::MissingField("num_inputs".to_string())));
+}
+
+#[test]
+fn export_and_import() {
+    use NetworkBuilder;
+
+    let mut net = NetworkBuilder::new()
+        .input("x0").input("x1")
+        .output("y")
+        .hidden("h").activation("h", Activation::Tanh).bias("h", 0.3)
+        .hidden("s").activation("s", Activation::Sin).connect("x0", "s", 0.9).connect("s", "y", -0.6)
+        .activation("y", Activation::Sigmoid).aggregation("y", Aggregation::Max)
+        .connect("x0", "h", 0.7).connect("x1", "h", -0.4).connect("h", "y", 1.5).connect_disabled("x1", "y", 0.2)
+        .build().unwrap();
+    let (config, genome) = export(&net).unwrap();
+    assert!(config.lines().any(|line| line.starts_with("feed_forward") && line.ends_with("= True")));
+
+    let mut imported = import(&config, &genome).unwrap();
+    // Both inputs use relu and are therefore represented by additional hidden nodes
+    assert_eq!(imported.nodes.len(), net.nodes.len() + 2);
+    for inputs in [vec![0.5, -1.0], vec![-2.0, 3.0], vec![1.0, 1.0]].iter() {
+        let expected = net.evaluate(inputs).unwrap()[0];
+        assert!((imported.evaluate(inputs).unwrap()[0] - expected).abs() < 1e-9);
+    }
+}
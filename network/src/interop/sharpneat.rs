@@ -0,0 +1,168 @@
+// SharpNEAT (2.x) stores genomes like this:
+//
+//     <Root>
+//       <ActivationFunctions><Fn id="0" name="SteepenedSigmoid" prob="1" /></ActivationFunctions>
+//       <Networks>
+//         <Network id="42" birthGen="7" fitness="3.2">
+//           <Nodes>
+//             <Node type="bias" id="0" /><Node type="in" id="1" /><Node type="out" id="2" /><Node type="hid" id="5" fnId="0" />
+//           </Nodes>
+//           <Connections><Con id="3" src="1" tgt="2" wght="0.57" /></Connections>
+//         </Network>
+//       </Networks>
+//     </Root>
+//
+// The bias node always outputs 1.0, so its connections are folded into the bias of their targets.
+// Input nodes pass on their value unchanged, every other node uses the activation function referenced by its fnId
+// (or the only function of the library if there is no fnId).
+
+use {
+    Float,
+    Network,
+    Node,
+    Gene,
+    Activation
+};
+use super::InteropError;
+use super::xml::{self, Element};
+
+fn activation_from_name(name: &str) -> Result<Activation, InteropError> {
+    match name {
+        "SteepenedSigmoid" => Ok(Activation::Sigmoid),
+        "Linear" => Ok(Activation::Identity),
+        "ReLU" => Ok(Activation::Relu),
+        "TanH" => Ok(Activation::Tanh),
+        other => Err(InteropError::Unsupported(format!("activation function '{}'", other)))
+    }
+}
+
+fn get_attribute<'a>(element: &'a Element, name: &str) -> Result<&'a str, InteropError> {
+    element.get_attribute(name).ok_or_else(|| InteropError::MissingField(format!("{} of {}", name, element.name)))
+}
+
+fn get_id(element: &Element, name: &str) -> Result<u64, InteropError> {
+    let value = get_attribute(element, name)?;
+    value.parse::<u64>().map_err(|_| InteropError::Malformed(format!("invalid id '{}'", value)))
+}
+
+/// Converts all networks of a SharpNEAT genome file
+pub fn import(document: &str) -> Result<Vec<Network>, InteropError> {
+    let root = xml::parse(document).map_err(InteropError::Malformed)?;
+
+    let mut functions = Vec::new();
+    if let Some(library) = root.get_child("ActivationFunctions") {
+        for function in library.get_children("Fn") {
+            functions.push((get_id(function, "id")?, get_attribute(function, "name")?));
+        }
+    }
+
+    if root.name == "Network" {
+        return Ok(vec![import_network(&root, &functions)?]);
+    }
+    let networks = root.get_child("Networks").ok_or_else(|| InteropError::MissingField("Networks".to_string()))?;
+    networks.get_children("Network").map(|network| import_network(network, &functions)).collect()
+}
+
+fn import_network(network: &Element, functions: &Vec<(u64, &str)>) -> Result<Network, InteropError> {
+    let node_elements = network.get_child("Nodes").ok_or_else(|| InteropError::MissingField("Nodes".to_string()))?;
+
+    // Sort the nodes into the layout of a Network (inputs, outputs, hidden nodes)
+    let mut ids = Vec::new();
+    let mut bias_ids = Vec::new();
+    let mut activations = Vec::new();
+    for node_type in ["in", "out", "hid"].iter() {
+        for node in node_elements.get_children("Node").filter(|node| node.get_attribute("type") == Some(node_type)) {
+            ids.push(get_id(node, "id")?);
+            activations.push(if *node_type == "in" {
+                Activation::Identity
+            } else {
+                let name = match node.get_attribute("fnId") {
+                    Some(_) => {
+                        let function_id = get_id(node, "fnId")?;
+                        functions.iter().find(|&&(id, _)| id == function_id).map(|&(_, name)| name).ok_or_else(|| {
+                            InteropError::UnknownNode(format!("activation function {}", function_id))
+                        })?
+                    },
+                    None if functions.len() == 1 => functions[0].1,
+                    None => "SteepenedSigmoid"
+                };
+                activation_from_name(name)?
+            });
+        }
+    }
+    for node in node_elements.get_children("Node") {
+        match node.get_attribute("type") {
+            Some("bias") => bias_ids.push(get_id(node, "id")?),
+            Some("in") | Some("out") | Some("hid") => {},
+            other => return Err(InteropError::Unsupported(format!("node type '{}'", other.unwrap_or(""))))
+        }
+    }
+
+    let inputs = node_elements.get_children("Node").filter(|node| node.get_attribute("type") == Some("in")).count();
+    let outputs = node_elements.get_children("Node").filter(|node| node.get_attribute("type") == Some("out")).count();
+    let mut nodes = Node::multiple_new(ids.len());
+    for (node, activation) in nodes.iter_mut().zip(activations.into_iter()) {
+        node.activation = activation;
+    }
+
+    let mut genome = Vec::new();
+    if let Some(connections) = network.get_child("Connections") {
+        for connection in connections.get_children("Con") {
+            let src = get_id(connection, "src")?;
+            let dest = get_id(connection, "tgt")?;
+            let weight_value = get_attribute(connection, "wght")?;
+            let weight = weight_value.parse::<Float>().map_err(|_| InteropError::Malformed(format!("invalid weight '{}'", weight_value)))?;
+
+            let dest = ids.iter().position(|id| *id == dest).ok_or_else(|| InteropError::UnknownNode(dest.to_string()))?;
+            if dest < inputs {
+                return Err(InteropError::Unsupported(format!("connection into input {}", ids[dest])));
+            }
+            if bias_ids.contains(&src) {
+                nodes[dest].bias += weight;
+            } else {
+                let src = ids.iter().position(|id| *id == src).ok_or_else(|| InteropError::UnknownNode(src.to_string()))?;
+                genome.push(Gene::with_weight(src, dest, false, weight));
+            }
+        }
+    }
+
+    Ok(Network {
+        genome: genome,
+        nodes: nodes,
+        inputs: inputs,
+        outputs: (inputs..inputs + outputs).collect()
+    })
+}
+
+#[test]
+fn import_genome() {
+    let document = r#"<?xml version="1.0" encoding="utf-8"?>
+        <Root>
+          <ActivationFunctions><Fn id="0" name="SteepenedSigmoid" prob="1" /><Fn id="1" name="Linear" prob="0" /></ActivationFunctions>
+          <Networks>
+            <Network id="42" birthGen="7" fitness="3.2">
+              <Nodes>
+                <Node type="bias" id="0" /><Node type="in" id="1" /><Node type="hid" id="7" fnId="1" /><Node type="out" id="2" fnId="1" />
+              </Nodes>
+              <Connections>
+                <Con id="3" src="1" tgt="7" wght="2" /><Con id="4" src="0" tgt="7" wght="-0.5" /><Con id="8" src="7" tgt="2" wght="1.5" />
+              </Connections>
+            </Network>
+          </Networks>
+        </Root>"#;
+    let mut networks = import(document).unwrap();
+    assert_eq!(networks.len(), 1);
+    let net = &mut networks[0];
+    assert_eq!((net.inputs, net.outputs.clone(), net.genome.len()), (1, vec![1], 2));
+    assert_eq!(net.nodes[2].bias, -0.5);
+    assert_eq!(net.evaluate(&vec![1.0]).unwrap(), vec![2.25]);
+}
+
+#[test]
+fn unsupported_genome() {
+    let document = r#"<Network><Nodes><Node type="in" id="1" /><Node type="out" id="2" fnId="0" /></Nodes></Network>"#;
+    assert_eq!(import(document), Err(InteropError::UnknownNode("activation function 0".to_string())));
+    let document = r#"<Root><ActivationFunctions><Fn id="0" name="LeakyReLU" /></ActivationFunctions>
+                      <Networks><Network><Nodes><Node type="out" id="2" /></Nodes></Network></Networks></Root>"#;
+    assert_eq!(import(document), Err(InteropError::Unsupported("activation function 'LeakyReLU'".to_string())));
+}
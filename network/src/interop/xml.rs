@@ -0,0 +1,163 @@
+/// Minimal XML reader that is just capable enough to read genome files
+///
+/// Text content, CDATA sections and namespaces are ignored; only elements and their attributes are kept.
+#[derive(Debug, PartialEq)]
+pub struct Element {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<Element>
+}
+
+impl Element {
+    pub fn get_attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.iter().find(|&&(ref key, _)| key == name).map(|&(_, ref value)| value.as_str())
+    }
+
+    /// All direct children with the given name
+    pub fn get_children<'a>(&'a self, name: &'a str) -> impl Iterator<Item=&'a Element> + 'a {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+
+    pub fn get_child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|child| child.name == name)
+    }
+}
+
+/// Parses a document and returns its root element
+pub fn parse(document: &str) -> Result<Element, String> {
+    let mut parser = Parser { data: document, position: 0 };
+    let mut stack: Vec<Element> = Vec::new();
+
+    loop {
+        match parser.data[parser.position..].find('<') {
+            Some(offset) => parser.position += offset,
+            None => return Err("unexpected end of document".to_string())
+        }
+
+        if parser.skip("<?") {
+            parser.skip_past("?>")?;
+        } else if parser.skip("<!--") {
+            parser.skip_past("-->")?;
+        } else if parser.skip("<![CDATA[") {
+            parser.skip_past("]]>")?;
+        } else if parser.skip("<!") {
+            parser.skip_past(">")?;
+        } else if parser.skip("</") {
+            let name = parser.read_name();
+            parser.skip_whitespace();
+            if !parser.skip(">") {
+                return Err(format!("malformed closing tag '{}'", name));
+            }
+            let element = stack.pop().ok_or_else(|| format!("unexpected closing tag '{}'", name))?;
+            if element.name != name {
+                return Err(format!("expected closing tag '{}' but found '{}'", element.name, name));
+            }
+            match stack.last_mut() {
+                Some(parent) => parent.children.push(element),
+                None => return Ok(element)
+            }
+        } else {
+            parser.position += 1;
+            let (element, closed) = parser.read_tag()?;
+            if closed {
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None => return Ok(element)
+                }
+            } else {
+                stack.push(element);
+            }
+        }
+    }
+}
+
+struct Parser<'a> {
+    data: &'a str,
+    position: usize
+}
+
+impl<'a> Parser<'a> {
+    fn skip(&mut self, token: &str) -> bool {
+        if self.data[self.position..].starts_with(token) {
+            self.position += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn skip_past(&mut self, token: &str) -> Result<(), String> {
+        match self.data[self.position..].find(token) {
+            Some(offset) => {
+                self.position += offset + token.len();
+                Ok(())
+            },
+            None => Err(format!("missing '{}'", token))
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.data[self.position..];
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    fn read_name(&mut self) -> String {
+        let rest = &self.data[self.position..];
+        let length = rest.find(|c: char| c.is_whitespace() || c == '>' || c == '/' || c == '=').unwrap_or(rest.len());
+        self.position += length;
+        rest[..length].to_string()
+    }
+
+    /// Reads the name and attributes of an opening tag and whether it closes itself
+    fn read_tag(&mut self) -> Result<(Element, bool), String> {
+        let mut element = Element {
+            name: self.read_name(),
+            attributes: Vec::new(),
+            children: Vec::new()
+        };
+        if element.name.is_empty() {
+            return Err("missing tag name".to_string());
+        }
+
+        loop {
+            self.skip_whitespace();
+            if self.skip("/>") {
+                return Ok((element, true));
+            } else if self.skip(">") {
+                return Ok((element, false));
+            }
+
+            let key = self.read_name();
+            self.skip_whitespace();
+            if key.is_empty() || !self.skip("=") {
+                return Err(format!("malformed attribute in tag '{}'", element.name));
+            }
+            self.skip_whitespace();
+            let quote = if self.skip("\"") { "\"" } else if self.skip("'") { "'" } else {
+                return Err(format!("unquoted attribute '{}' in tag '{}'", key, element.name));
+            };
+            let length = self.data[self.position..].find(quote).ok_or_else(|| format!("unterminated attribute '{}'", key))?;
+            let value = unescape(&self.data[self.position..self.position + length]);
+            self.position += length + 1;
+            element.attributes.push((key, value));
+        }
+    }
+}
+
+fn unescape(value: &str) -> String {
+    value.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[test]
+fn parse_document() {
+    let root = parse("<?xml version=\"1.0\"?>\n<!-- genomes -->\n<Root a='1'><Child b=\"x &amp; y\"/>text<Child></Child></Root>").unwrap();
+    assert_eq!(root.name, "Root");
+    assert_eq!(root.get_attribute("a"), Some("1"));
+    assert_eq!(root.get_children("Child").count(), 2);
+    assert_eq!(root.children[0].get_attribute("b"), Some("x & y"));
+    assert!(parse("<Root><Child></Root>").is_err());
+}
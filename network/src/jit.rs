@@ -0,0 +1,431 @@
+// Native code generation for networks (requires the "jit" feature)
+//
+// The frozen form of a network is translated into a single Cranelift function that reads the node outputs and
+// memory cells from the same buffers the frozen interpreter uses. Builds without the feature, hosts that Cranelift
+// doesn't support and networks that fail to compile keep using the interpreter, so a CompiledNetwork can always be
+// used in place of Network::evaluate().
+
+#[cfg(feature = "jit")]
+use std::{mem, slice};
+
+use {
+    Float,
+    Network,
+    OwnedFrozenNetwork,
+    EvaluationError
+};
+#[cfg(feature = "jit")]
+use {
+    Activation,
+    Aggregation,
+    FrozenNetwork,
+    Gate
+};
+#[cfg(feature = "jit")]
+use memory::step_cell;
+#[cfg(feature = "jit")]
+use math;
+
+#[cfg(feature = "jit")]
+use cranelift_codegen::ir::{types, AbiParam, FuncRef, InstBuilder, MemFlags, StackSlotData, StackSlotKind, Type, Value};
+#[cfg(feature = "jit")]
+use cranelift_codegen::ir::condcodes::FloatCC;
+#[cfg(feature = "jit")]
+use cranelift_codegen::settings::{self, Configurable};
+#[cfg(feature = "jit")]
+use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext};
+#[cfg(feature = "jit")]
+use cranelift_jit::{JITBuilder, JITModule};
+#[cfg(feature = "jit")]
+use cranelift_module::{default_libcall_names, Linkage, Module};
+
+/// Signature of the generated code: inputs, node outputs, memory cell states and the buffer for the network outputs
+pub type JitFunction = unsafe extern "C" fn(*const Float, *mut Float, *mut Float, *mut Float);
+
+/// Network that is evaluated by native code if possible and by the frozen interpreter otherwise
+///
+/// Like a frozen network it continues from the state the network had when it was compiled.
+pub struct CompiledNetwork {
+    frozen: OwnedFrozenNetwork,
+    #[cfg(feature = "jit")]
+    native: Option<NativeCode>
+}
+
+impl CompiledNetwork {
+    /// Whether the network runs as native code (false if it fell back to the interpreter)
+    pub fn is_native(&self) -> bool {
+        self.get_function().is_some()
+    }
+
+    /// Generated function, which has to be called with buffers of the sizes given by get_frozen()
+    pub fn get_function(&self) -> Option<JitFunction> {
+        #[cfg(feature = "jit")]
+        { self.native.as_ref().map(|native| native.function) }
+        #[cfg(not(feature = "jit"))]
+        { None }
+    }
+
+    /// Frozen form of the network which holds the description and state used by both backends
+    pub fn get_frozen(&self) -> &OwnedFrozenNetwork {
+        &self.frozen
+    }
+
+    pub fn evaluate(&mut self, inputs: &[Float]) -> Result<Vec<Float>, EvaluationError> {
+        let mut outputs = vec![0.0; self.frozen.get_network().outputs.len()];
+        self.evaluate_into(inputs, &mut outputs)?;
+        Ok(outputs)
+    }
+
+    /// Evaluates the network without allocating and writes the results into outputs
+    pub fn evaluate_into(&mut self, inputs: &[Float], outputs: &mut [Float]) -> Result<(), EvaluationError> {
+        let function = self.get_function();
+        let (network, mut state) = self.frozen.split();
+        match function {
+            Some(function) => {
+                if inputs.len() != network.inputs {
+                    return Err(EvaluationError::InputSizeMismatch);
+                }
+                if outputs.len() != network.outputs.len() {
+                    return Err(EvaluationError::BufferSizeMismatch);
+                }
+                unsafe {
+                    function(inputs.as_ptr(), state.outputs.as_mut_ptr(), state.cells.as_mut_ptr(), outputs.as_mut_ptr());
+                }
+                Ok(())
+            },
+            None => network.evaluate(&mut state, inputs, outputs)
+        }
+    }
+
+    /// Removes all recurrent data
+    pub fn reset(&mut self) {
+        self.frozen.reset();
+    }
+}
+
+impl Network {
+    /// Compiles the network to native code (falling back to the interpreter if that isn't possible)
+    pub fn compile(&self) -> CompiledNetwork {
+        let frozen = self.freeze();
+        CompiledNetwork {
+            #[cfg(feature = "jit")]
+            native: NativeCode::new(&frozen.get_network()).ok(),
+            frozen: frozen
+        }
+    }
+}
+
+/// Executable memory of a generated function, which is released together with it
+#[cfg(feature = "jit")]
+struct NativeCode {
+    module: Option<JITModule>,
+    function: JitFunction
+}
+
+#[cfg(feature = "jit")]
+impl Drop for NativeCode {
+    fn drop(&mut self) {
+        if let Some(module) = self.module.take() {
+            unsafe { module.free_memory() };
+        }
+    }
+}
+
+#[cfg(all(feature = "jit", feature = "double_precision"))]
+const FLOAT: Type = types::F64;
+#[cfg(all(feature = "jit", feature = "single_precision"))]
+const FLOAT: Type = types::F32;
+
+#[cfg(all(feature = "jit", feature = "double_precision"))]
+fn constant(builder: &mut FunctionBuilder, value: Float) -> Value {
+    builder.ins().f64const(value)
+}
+
+#[cfg(all(feature = "jit", feature = "single_precision"))]
+fn constant(builder: &mut FunctionBuilder, value: Float) -> Value {
+    builder.ins().f32const(value)
+}
+
+// Functions that are too complex to be generated inline and are called by the generated code instead
+
+#[cfg(feature = "jit")]
+extern "C" fn neat_exp(x: Float) -> Float {
+    math::exp(x)
+}
+
+#[cfg(feature = "jit")]
+extern "C" fn neat_tanh(x: Float) -> Float {
+    math::tanh(x)
+}
+
+#[cfg(feature = "jit")]
+extern "C" fn neat_sin(x: Float) -> Float {
+    math::sin(x)
+}
+
+#[cfg(feature = "jit")]
+unsafe extern "C" fn neat_median(inputs: *mut Float, length: usize) -> Float {
+    Aggregation::Median.aggregate_in_place(slice::from_raw_parts_mut(inputs, length))
+}
+
+#[cfg(feature = "jit")]
+unsafe extern "C" fn neat_step_cell(state: *mut Float, input_gate: Float, forget_gate: Float, output_gate: Float, input: Float) -> Float {
+    step_cell(&mut *state, input_gate, forget_gate, output_gate, input)
+}
+
+/// Imported helper functions as seen from inside of the generated function
+#[cfg(feature = "jit")]
+struct Helpers {
+    exp: FuncRef,
+    tanh: FuncRef,
+    sin: FuncRef,
+    median: FuncRef,
+    step_cell: FuncRef
+}
+
+#[cfg(feature = "jit")]
+impl NativeCode {
+    fn new(network: &FrozenNetwork) -> Result<NativeCode, String> {
+        let mut flags = settings::builder();
+        flags.set("opt_level", "speed").map_err(|error| error.to_string())?;
+        flags.set("is_pic", "false").map_err(|error| error.to_string())?;
+        let isa = cranelift_native::builder()?
+            .finish(settings::Flags::new(flags))
+            .map_err(|error| error.to_string())?;
+
+        let mut jit_builder = JITBuilder::with_isa(isa, default_libcall_names());
+        jit_builder.symbol("neat_exp", neat_exp as *const u8);
+        jit_builder.symbol("neat_tanh", neat_tanh as *const u8);
+        jit_builder.symbol("neat_sin", neat_sin as *const u8);
+        jit_builder.symbol("neat_median", neat_median as *const u8);
+        jit_builder.symbol("neat_step_cell", neat_step_cell as *const u8);
+        let mut module = JITModule::new(jit_builder);
+
+        match NativeCode::generate(&mut module, network) {
+            Ok(function) => Ok(NativeCode { module: Some(module), function: function }),
+            Err(error) => {
+                unsafe { module.free_memory() };
+                Err(error)
+            }
+        }
+    }
+
+    fn generate(module: &mut JITModule, network: &FrozenNetwork) -> Result<JitFunction, String> {
+        let pointer = module.target_config().pointer_type();
+        let size = mem::size_of::<Float>() as i32;
+
+        let mut unary = module.make_signature();
+        unary.params.push(AbiParam::new(FLOAT));
+        unary.returns.push(AbiParam::new(FLOAT));
+        let mut median = module.make_signature();
+        median.params.extend_from_slice(&[AbiParam::new(pointer), AbiParam::new(pointer)]);
+        median.returns.push(AbiParam::new(FLOAT));
+        let mut step_cell = module.make_signature();
+        step_cell.params.push(AbiParam::new(pointer));
+        step_cell.params.extend((0..4).map(|_| AbiParam::new(FLOAT)));
+        step_cell.returns.push(AbiParam::new(FLOAT));
+
+        let mut import = |name: &str, signature| module.declare_function(name, Linkage::Import, signature).map_err(|error| error.to_string());
+        let exp = import("neat_exp", &unary)?;
+        let tanh = import("neat_tanh", &unary)?;
+        let sin = import("neat_sin", &unary)?;
+        let median = import("neat_median", &median)?;
+        let step_cell = import("neat_step_cell", &step_cell)?;
+
+        let mut context = module.make_context();
+        context.func.signature.params.extend((0..4).map(|_| AbiParam::new(pointer)));
+        let id = module.declare_function("evaluate", Linkage::Local, &context.func.signature).map_err(|error| error.to_string())?;
+
+        let mut function_context = FunctionBuilderContext::new();
+        {
+            let mut builder = FunctionBuilder::new(&mut context.func, &mut function_context);
+            let helpers = Helpers {
+                exp: module.declare_func_in_func(exp, builder.func),
+                tanh: module.declare_func_in_func(tanh, builder.func),
+                sin: module.declare_func_in_func(sin, builder.func),
+                median: module.declare_func_in_func(median, builder.func),
+                step_cell: module.declare_func_in_func(step_cell, builder.func)
+            };
+            let scratch = builder.create_sized_stack_slot(StackSlotData::new(
+                StackSlotKind::ExplicitSlot, (network.scratch_size.max(1) * size as usize) as u32, 3));
+
+            let block = builder.create_block();
+            builder.append_block_params_for_function_params(block);
+            builder.switch_to_block(block);
+            builder.seal_block(block);
+            let (inputs, state, cells, outputs) = {
+                let parameters = builder.block_params(block);
+                (parameters[0], parameters[1], parameters[2], parameters[3])
+            };
+            let flags = MemFlags::trusted();
+
+            for node in network.nodes.iter() {
+                let mut values = Vec::new();
+                let zero = constant(&mut builder, 0.0);
+                let mut gates = [zero; 3];
+                if let Some(input) = node.input {
+                    values.push(builder.ins().load(FLOAT, flags, inputs, input as i32 * size));
+                }
+                for gene in network.genes[node.first_gene..node.first_gene + node.gene_count].iter() {
+                    let source = builder.ins().load(FLOAT, flags, state, gene.source as i32 * size);
+                    let weight = constant(&mut builder, gene.weight);
+                    let contribution = builder.ins().fmul(source, weight);
+                    match gene.gate {
+                        None => values.push(contribution),
+                        Some(gate) => gates[gate as usize] = builder.ins().fadd(gates[gate as usize], contribution)
+                    }
+                }
+
+                let aggregated = match node.aggregation {
+                    Aggregation::Median => {
+                        for (i, value) in values.iter().enumerate() {
+                            builder.ins().stack_store(*value, scratch, i as i32 * size);
+                        }
+                        let address = builder.ins().stack_addr(pointer, scratch, 0);
+                        let length = builder.ins().iconst(pointer, values.len() as i64);
+                        let call = builder.ins().call(helpers.median, &[address, length]);
+                        builder.inst_results(call)[0]
+                    },
+                    aggregation => aggregate(&mut builder, aggregation, &values)
+                };
+                let bias = constant(&mut builder, node.bias);
+                let input = builder.ins().fadd(aggregated, bias);
+
+                let output = match node.memory {
+                    Some(cell) => {
+                        let address = builder.ins().iadd_imm(cells, cell as i64 * size as i64);
+                        let arguments = [address, gates[Gate::Input as usize], gates[Gate::Forget as usize], gates[Gate::Output as usize], input];
+                        let call = builder.ins().call(helpers.step_cell, &arguments);
+                        builder.inst_results(call)[0]
+                    },
+                    None => activate(&mut builder, &helpers, node.activation, input)
+                };
+                builder.ins().store(flags, output, state, node.output as i32 * size);
+            }
+
+            for (i, output) in network.outputs.iter().enumerate() {
+                let value = builder.ins().load(FLOAT, flags, state, *output as i32 * size);
+                builder.ins().store(flags, value, outputs, i as i32 * size);
+            }
+            builder.ins().return_(&[]);
+            builder.finalize();
+        }
+
+        module.define_function(id, &mut context).map_err(|error| error.to_string())?;
+        module.clear_context(&mut context);
+        module.finalize_definitions().map_err(|error| error.to_string())?;
+        Ok(unsafe { mem::transmute::<*const u8, JitFunction>(module.get_finalized_function(id)) })
+    }
+}
+
+/// Generates the same folds as Aggregation::aggregate() (which keep the NaN handling of Float::max() and Float::min())
+#[cfg(feature = "jit")]
+fn aggregate(builder: &mut FunctionBuilder, aggregation: Aggregation, values: &[Value]) -> Value {
+    if values.is_empty() {
+        return constant(builder, 0.0);
+    }
+
+    match aggregation {
+        Aggregation::Sum | Aggregation::Mean => {
+            let zero = constant(builder, 0.0);
+            let sum = values.iter().fold(zero, |acc, value| builder.ins().fadd(acc, *value));
+            if aggregation == Aggregation::Mean {
+                let count = constant(builder, values.len() as Float);
+                builder.ins().fdiv(sum, count)
+            } else {
+                sum
+            }
+        },
+        Aggregation::Product => {
+            let one = constant(builder, 1.0);
+            values.iter().fold(one, |acc, value| builder.ins().fmul(acc, *value))
+        },
+        Aggregation::Max | Aggregation::Min | Aggregation::MaxAbs => values[1..].iter().fold(values[0], |acc, value| {
+            let replace = match aggregation {
+                Aggregation::MaxAbs => {
+                    let (value_abs, acc_abs) = (builder.ins().fabs(*value), builder.ins().fabs(acc));
+                    builder.ins().fcmp(FloatCC::GreaterThan, value_abs, acc_abs)
+                },
+                _ => {
+                    let condition = if aggregation == Aggregation::Max { FloatCC::GreaterThan } else { FloatCC::LessThan };
+                    let better = builder.ins().fcmp(condition, *value, acc);
+                    let invalid = builder.ins().fcmp(FloatCC::Unordered, acc, acc);
+                    builder.ins().bor(better, invalid)
+                }
+            };
+            builder.ins().select(replace, *value, acc)
+        }),
+        Aggregation::Median => unreachable!("The median is calculated by a helper function")
+    }
+}
+
+#[cfg(feature = "jit")]
+fn activate(builder: &mut FunctionBuilder, helpers: &Helpers, activation: Activation, x: Value) -> Value {
+    let call = |builder: &mut FunctionBuilder, function: FuncRef, argument: Value| {
+        let call = builder.ins().call(function, &[argument]);
+        builder.inst_results(call)[0]
+    };
+
+    match activation {
+        Activation::Relu => {
+            let zero = constant(builder, 0.0);
+            let positive = builder.ins().fcmp(FloatCC::GreaterThan, x, zero);
+            builder.ins().select(positive, x, zero)
+        },
+        Activation::Sigmoid => {
+            let steepness = constant(builder, -4.9);
+            let exponent = builder.ins().fmul(steepness, x);
+            let power = call(builder, helpers.exp, exponent);
+            let one = constant(builder, 1.0);
+            let denominator = builder.ins().fadd(one, power);
+            builder.ins().fdiv(one, denominator)
+        },
+        Activation::Tanh => call(builder, helpers.tanh, x),
+        Activation::Identity => x,
+        Activation::Gaussian => {
+            let negated = builder.ins().fneg(x);
+            let exponent = builder.ins().fmul(negated, x);
+            call(builder, helpers.exp, exponent)
+        },
+        Activation::Sin => call(builder, helpers.sin, x),
+        Activation::Abs => {
+            let zero = constant(builder, 0.0);
+            let negative = builder.ins().fcmp(FloatCC::LessThan, x, zero);
+            let negated = builder.ins().fneg(x);
+            builder.ins().select(negative, negated, x)
+        }
+    }
+}
+
+#[test]
+fn compiled_evaluation() {
+    use {NetworkBuilder, Activation, Aggregation, Gate};
+
+    let mut builder = NetworkBuilder::new()
+        .input("a").input("b").output("y").output("z").hidden("m").memory("m")
+        .connect("a", "m", 0.7).connect_gate("b", "m", Gate::Input, 2.0).connect_gate("a", "m", Gate::Forget, -1.0)
+        .connect("m", "y", 1.2).connect("y", "y", 0.5).connect("z", "y", -0.3);
+    // One hidden node for every combination of aggregation and activation
+    for (i, aggregation) in Aggregation::all().iter().enumerate() {
+        for (j, activation) in Activation::all().iter().enumerate() {
+            let name = format!("h{}_{}", i, j);
+            builder = builder.hidden(&name).aggregation(&name, *aggregation).activation(&name, *activation).bias(&name, 0.1 * j as Float)
+                .connect("a", &name, 0.5 + i as Float).connect("b", &name, -1.5).connect("m", &name, 0.25)
+                .connect(&name, "z", 0.05).connect(&name, &name, 0.2);
+        }
+    }
+    let mut net = builder.build().unwrap();
+    net.evaluate(&vec![0.3, 0.1]).unwrap();
+
+    let mut compiled = net.compile();
+    assert_eq!(compiled.is_native(), cfg!(feature = "jit"));
+    for i in 0..10 {
+        let inputs = vec![(i as Float * 0.7).sin(), (i as Float * 0.3).cos()];
+        assert_eq!(compiled.evaluate(&inputs).unwrap(), net.evaluate(&inputs).unwrap());
+    }
+    compiled.reset();
+    net.reset();
+    assert_eq!(compiled.evaluate(&[1.0, -1.0]).unwrap(), net.evaluate(&vec![1.0, -1.0]).unwrap());
+    assert!(compiled.evaluate(&[1.0]).is_err());
+    assert!(compiled.evaluate_into(&[1.0, 2.0], &mut [0.0]).is_err());
+}
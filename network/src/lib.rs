@@ -1,15 +1,115 @@
 #![allow(dead_code)]
+#![cfg_attr(not(feature = "std"), no_std)]
+#[cfg(feature = "alloc")]
+#[macro_use]
+extern crate alloc;
+#[cfg(feature = "training")]
 extern crate rand;
+#[cfg(feature = "serialization")]
 extern crate rustc_serialize;
+#[cfg(feature = "libm")]
+extern crate libm;
+#[cfg(feature = "jit")]
+extern crate cranelift_codegen;
+#[cfg(feature = "jit")]
+extern crate cranelift_frontend;
+#[cfg(feature = "jit")]
+extern crate cranelift_jit;
+#[cfg(feature = "jit")]
+extern crate cranelift_module;
+#[cfg(feature = "jit")]
+extern crate cranelift_native;
+
+#[cfg(all(not(feature = "std"), not(feature = "libm")))]
+compile_error!("neatwork requires either the std or the libm feature for its math functions");
 
 mod type_def;
 pub use type_def::*;
 
+mod math;
+
+mod activation;
+pub use activation::Activation;
+
+mod aggregation;
+pub use aggregation::Aggregation;
+
+mod memory;
+pub use memory::Gate;
+#[cfg(feature = "std")]
+pub use memory::MemoryCell;
+
+mod frozen;
+pub use frozen::{FrozenNetwork, FrozenNode, FrozenGene, FrozenState};
+#[cfg(feature = "alloc")]
+pub use frozen::OwnedFrozenNetwork;
+
+#[cfg(feature = "std")]
 mod node;
+#[cfg(feature = "std")]
 pub use node::Node;
 
+#[cfg(feature = "std")]
 mod gene;
+#[cfg(feature = "std")]
 pub use gene::Gene;
 
+#[cfg(feature = "std")]
+mod trace;
+#[cfg(feature = "std")]
+pub use trace::{Trace, TraceStep, NodeTrace, GeneTrace};
+
+#[cfg(feature = "std")]
 mod network;
-pub use network::{Network, EvaluationError};
+#[cfg(feature = "std")]
+pub use network::Network;
+
+#[cfg(feature = "std")]
+mod builder;
+#[cfg(feature = "std")]
+pub use builder::{NetworkBuilder, BuildError};
+
+#[cfg(feature = "std")]
+mod text;
+#[cfg(feature = "std")]
+pub use text::ParseError;
+
+#[cfg(feature = "std")]
+mod binary;
+#[cfg(feature = "std")]
+pub use binary::{encode_population, decode_population, BinaryError};
+
+#[cfg(feature = "std")]
+mod expression;
+#[cfg(feature = "std")]
+pub use expression::{Expression, ExpressionError};
+
+#[cfg(feature = "training")]
+mod analysis;
+
+#[cfg(feature = "training")]
+mod robustness;
+#[cfg(feature = "training")]
+pub use robustness::{Perturbation, PerturbedNetwork, RobustnessReport, RobustnessError};
+
+#[cfg(feature = "std")]
+mod plan;
+
+#[cfg(feature = "std")]
+mod quantized;
+#[cfg(feature = "std")]
+pub use quantized::{QuantizedNetwork, QuantizationError};
+
+#[cfg(feature = "std")]
+mod jit;
+#[cfg(feature = "std")]
+pub use jit::{CompiledNetwork, JitFunction};
+
+#[cfg(feature = "std")]
+pub mod interop;
+
+#[cfg(feature = "std")]
+mod dot;
+
+#[cfg(feature = "ffi")]
+pub mod ffi;
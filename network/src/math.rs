@@ -0,0 +1,28 @@
+// Math functions that come from std if it is available and from libm otherwise
+
+use Float;
+
+#[cfg(feature = "std")]
+pub fn exp(x: Float) -> Float {
+    x.exp()
+}
+
+#[cfg(feature = "std")]
+pub fn tanh(x: Float) -> Float {
+    x.tanh()
+}
+
+#[cfg(feature = "std")]
+pub fn sin(x: Float) -> Float {
+    x.sin()
+}
+
+#[cfg(all(not(feature = "std"), feature = "libm", feature = "double_precision"))]
+pub use libm::{exp, tanh, sin};
+
+#[cfg(all(not(feature = "std"), feature = "libm", feature = "single_precision"))]
+pub use libm::{expf as exp, tanhf as tanh, sinf as sin};
+
+pub fn abs(x: Float) -> Float {
+    if x < 0.0 { -x } else { x }
+}
@@ -0,0 +1,138 @@
+use Float;
+use math;
+
+/// Gates of a memory cell that can be targeted by a gene instead of the regular node input
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(RustcDecodable, RustcEncodable))]
+pub enum Gate {
+    /// Controls how much of the node input is written into the cell state
+    Input,
+    /// Controls how much of the previous cell state is kept
+    Forget,
+    /// Controls how much of the cell state is exposed as the node output
+    Output
+}
+
+impl Gate {
+    /// List of all available gates
+    pub fn all() -> [Gate; 3] {
+        [Gate::Input, Gate::Forget, Gate::Output]
+    }
+
+    /// Lowercase name of the gate
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Gate::Input => "input",
+            Gate::Forget => "forget",
+            Gate::Output => "output"
+        }
+    }
+
+    /// Looks up a gate by its name()
+    pub fn from_name(name: &str) -> Option<Gate> {
+        Gate::all().iter().find(|gate| gate.name() == name).cloned()
+    }
+}
+
+/// Updates the state of a memory cell from the gate input sums and the node input and returns the cell output
+///
+/// Every gate squashes the sum of its inputs with the logistic function, so a gate without inputs is half open.
+pub fn step_cell(state: &mut Float, input_gate: Float, forget_gate: Float, output_gate: Float, input: Float) -> Float {
+    let input_gate = logistic(input_gate);
+    let forget_gate = logistic(forget_gate);
+    let output_gate = logistic(output_gate);
+
+    *state = forget_gate * *state + input_gate * math::tanh(input);
+    output_gate * math::tanh(*state)
+}
+
+fn logistic(x: Float) -> Float {
+    1.0 / (1.0 + math::exp(-x))
+}
+
+/// LSTM-like memory that can be attached to a node to keep data over many evaluations
+///
+/// Every gate sums its own inputs (delivered by genes targeting that gate) and squashes them with the logistic function.
+/// A gate without any incoming genes is therefore half open.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(RustcDecodable, RustcEncodable))]
+pub struct MemoryCell {
+    /// Internal state that is carried over from one evaluation to the next
+    pub state: Float,
+    /// Inputs of the input gate that are summed upon evaluation
+    pub input_gate: Vec<Float>,
+    /// Inputs of the forget gate that are summed upon evaluation
+    pub forget_gate: Vec<Float>,
+    /// Inputs of the output gate that are summed upon evaluation
+    pub output_gate: Vec<Float>
+}
+
+#[cfg(feature = "std")]
+impl MemoryCell {
+    /// Initializes an empty memory cell
+    pub fn new() -> MemoryCell {
+        MemoryCell {
+            state: 0.0,
+            input_gate: Vec::new(),
+            forget_gate: Vec::new(),
+            output_gate: Vec::new()
+        }
+    }
+
+    /// Adds a value to the inputs of the given gate
+    pub fn push(&mut self, gate: Gate, value: Float) {
+        match gate {
+            Gate::Input => self.input_gate.push(value),
+            Gate::Forget => self.forget_gate.push(value),
+            Gate::Output => self.output_gate.push(value)
+        }
+    }
+
+    /// Feeds the node input into the cell, updates the state and returns the resulting output
+    pub fn step(&mut self, input: Float) -> Float {
+        let input_gate = sum_gate(&mut self.input_gate);
+        let forget_gate = sum_gate(&mut self.forget_gate);
+        let output_gate = sum_gate(&mut self.output_gate);
+        step_cell(&mut self.state, input_gate, forget_gate, output_gate, input)
+    }
+
+    /// Removes the stored state and all pending gate inputs
+    pub fn reset(&mut self) {
+        self.state = 0.0;
+        self.input_gate.clear();
+        self.forget_gate.clear();
+        self.output_gate.clear();
+    }
+}
+
+/// Sums and clears the inputs of a gate
+#[cfg(feature = "std")]
+fn sum_gate(inputs: &mut Vec<Float>) -> Float {
+    let sum = inputs.iter().fold(0.0, |acc, input| acc + input);
+    inputs.clear();
+    sum
+}
+
+#[test]
+fn closed_input_gate_keeps_state() {
+    let mut cell = MemoryCell::new();
+    cell.push(Gate::Input, 100.0);
+    cell.step(1.0);
+    let state = cell.state;
+    for _ in 0..10 {
+        cell.push(Gate::Input, -100.0);
+        cell.push(Gate::Forget, 100.0);
+        cell.step(-1.0);
+    }
+    assert!((cell.state - state).abs() < 1e-6);
+}
+
+#[test]
+fn reset_state() {
+    let mut cell = MemoryCell::new();
+    cell.step(1.0);
+    cell.push(Gate::Output, 1.0);
+    cell.reset();
+    assert_eq!(cell, MemoryCell::new());
+}
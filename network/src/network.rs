@@ -1,4 +1,7 @@
+#[cfg(feature = "serialization")]
 use rustc_serialize::json;
+#[cfg(feature = "training")]
+use rand::{thread_rng, Rng};
 
 use {
     GID,
@@ -6,22 +9,22 @@ use {
     Node,
     Gene,
     Float,
-    Link
+    Link,
+    Trace,
+    TraceStep,
+    NodeTrace,
+    GeneTrace,
+    EvaluationError
 };
 
-#[derive(Debug)]
-pub enum EvaluationError {
-    InputSizeMismatch,
-    Unknown
-}
-
 pub type Genome = Vec<Gene>;
 
 /// Structure representing a network or lifeform inside the population
 ///
 /// The nodes with the NIDs from 0 to x represent the inputs where x is the number of inputs
 /// The nodes with the NIDs from nodes.len()-x to nodes.len() represent the outputs where x is the number of outputs
-#[derive(Debug, RustcDecodable, RustcEncodable, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(RustcDecodable, RustcEncodable))]
 pub struct Network {
     /// HashMap that contains the genes and their respective GIDs
     pub genome: Genome,
@@ -35,11 +38,18 @@ pub struct Network {
 }
 
 impl Network {
+    #[cfg(feature = "training")]
     pub fn new_empty(inputs: usize, outputs: usize) -> Network {
+        Network::new_empty_with_rng(inputs, outputs, &mut thread_rng())
+    }
+
+    /// Like new_empty() but draws the gene weights from the given generator
+    #[cfg(feature = "training")]
+    pub fn new_empty_with_rng<R: Rng>(inputs: usize, outputs: usize, rng: &mut R) -> Network {
         Network {
             genome: (0..inputs).flat_map(|i| {
                 (inputs..inputs+outputs).map(|o| {
-                    Gene::random(i, o, false)
+                    Gene::random_with_rng(i, o, false, rng)
                 }).collect::<Vec<_>>()
             }).collect(),
             nodes: Node::multiple_new(inputs+outputs),
@@ -48,11 +58,15 @@ impl Network {
         }
     }
 
-    fn import(data: String) -> Result<Network, json::DecoderError> {
+    /// Decodes a network from JSON (see to_binary() for a more compact alternative)
+    #[cfg(feature = "serialization")]
+    pub fn import(data: String) -> Result<Network, json::DecoderError> {
         json::decode(&data)
     }
 
-    fn export(&self) -> Result<String, json::EncoderError> {
+    /// Encodes the network (including its current state) as JSON
+    #[cfg(feature = "serialization")]
+    pub fn export(&self) -> Result<String, json::EncoderError> {
         json::encode(self)
     }
 
@@ -67,16 +81,19 @@ impl Network {
     }
 
     /// Execute a gene => grab the output of the src, evaluate the gene and add the resulting value to the target nodes input
-    fn process_gene(&mut self, link: Link, node_id: NID, gene_id: GID) {
+    ///
+    /// Returns the value that has been passed on to the target node
+    fn process_gene(&mut self, link: Link, node_id: NID, gene_id: GID) -> Float {
         if link.1 != node_id { panic!("Link target does not match current node ({}): {} -> {}", node_id, link.0, link.1); }
         let output = self.nodes.get(link.0).expect("Node disappeared!").output;
-        self.nodes.get_mut(link.1).expect("Node disappeared!").inputs.push(
-            self.genome.get(gene_id).expect("Gene disappeared!").evaluate(output)
-        );
+        let gene = self.genome.get(gene_id).expect("Gene disappeared!");
+        let contribution = gene.evaluate(output);
+        self.nodes.get_mut(link.1).expect("Node disappeared!").push_input(gene.gate, contribution);
+        contribution
     }
 
-    /// Calculate a node and all its dependencies
-    fn recursive_calc_node(&mut self, node_id: NID, visited: &mut Vec<NID>) -> Float {
+    /// Calculate a node and all its dependencies (recording all values into the trace step if one is given)
+    fn recursive_calc_node(&mut self, node_id: NID, visited: &mut Vec<NID>, trace: &mut Option<TraceStep>) -> Float {
         let executed = self.nodes.get_mut(node_id).expect("Node disappeared!").executed;
         if visited.contains(&node_id) || executed {
             self.nodes.get_mut(node_id).expect("Node disappeared!").output
@@ -96,18 +113,68 @@ impl Network {
 
                 // Calculate the values of the nodes that are on the other end of the connection
                 for link in dependend_links.iter() {
-                    self.recursive_calc_node(link.0, visited); //TODO prevent infinite loop (3->4 and 4->3)
+                    self.recursive_calc_node(link.0, visited, trace); //TODO prevent infinite loop (3->4 and 4->3)
                 }
 
                 // Push the outputs through the genes (apply weights) and insert them into the target/current node
                 for (gene_id, link) in dependencies.iter().zip(dependend_links.iter()) {
-                    self.process_gene(*link, node_id, *gene_id);
+                    let contribution = self.process_gene(*link, node_id, *gene_id);
+                    if let Some(ref mut step) = *trace {
+                        step.genes.push(GeneTrace { gene: *gene_id, link: *link, contribution: contribution });
+                    }
                 }
             }
-            self.nodes.get_mut(node_id).expect("Node disappeared!").evaluate()
+
+            let node = self.nodes.get_mut(node_id).expect("Node disappeared!");
+            match *trace {
+                Some(ref mut step) => {
+                    let input = node.get_input();
+                    let output = node.evaluate();
+                    step.nodes.push(NodeTrace { node: node_id, input: input, output: output });
+                    output
+                },
+                None => node.evaluate()
+            }
         }
     }
 
+    /// Whether or not the enabled genes form at least one cycle (including genes that link a node to itself)
+    pub fn is_recurrent(&self) -> bool {
+        let mut successors = vec![Vec::new(); self.nodes.len()];
+        for gene in self.genome.iter().filter(|gene| !gene.disabled) {
+            successors[gene.link.0].push(gene.link.1);
+        }
+
+        // Depth first search with an explicit stack of (node, next successor) so long chains can't overflow the call stack
+        // 0 = unvisited, 1 = on the current path, 2 = done
+        let mut state = vec![0u8; self.nodes.len()];
+        let mut stack = Vec::new();
+        for start in 0..self.nodes.len() {
+            if state[start] != 0 {
+                continue;
+            }
+            state[start] = 1;
+            stack.push((start, 0));
+            while let Some((node, index)) = stack.pop() {
+                match successors[node].get(index) {
+                    Some(&target) => {
+                        stack.push((node, index + 1));
+                        match state[target] {
+                            0 => {
+                                state[target] = 1;
+                                stack.push((target, 0));
+                            },
+                            1 => return true,
+                            _ => {}
+                        }
+                    },
+                    None => state[node] = 2
+                }
+            }
+        }
+        false
+    }
+
     pub fn get_size(&self) -> (GID, NID) {
         let non_disabled_genes = self.genome.iter().fold(0, |acc, gene| {
             if gene.disabled {
@@ -123,6 +190,21 @@ impl Network {
     ///
     /// This might eventually leave some remaining recurrent data in the network behind for the next evaluation.
     pub fn evaluate(&mut self, inputs: &Vec<Float>) -> Result<Vec<Float>, EvaluationError> {
+        self.evaluate_with_trace(inputs, &mut None)
+    }
+
+    /// Evaluate the network like evaluate() does and append the values of every node and gene as a new step to the trace.
+    pub fn evaluate_traced(&mut self, inputs: &Vec<Float>, trace: &mut Trace) -> Result<Vec<Float>, EvaluationError> {
+        let mut step = Some(TraceStep::new(inputs));
+        let outputs = self.evaluate_with_trace(inputs, &mut step)?;
+        if let Some(mut step) = step {
+            step.outputs = outputs.clone();
+            trace.steps.push(step);
+        }
+        Ok(outputs)
+    }
+
+    fn evaluate_with_trace(&mut self, inputs: &Vec<Float>, trace: &mut Option<TraceStep>) -> Result<Vec<Float>, EvaluationError> {
         if !(inputs.len() == self.inputs) {
             return Err(EvaluationError::InputSizeMismatch);
         }
@@ -138,7 +220,7 @@ impl Network {
         let outputs = self.outputs.clone(); // This assumes that outputs is NEVER modified whilst this function runs
         let output_values = outputs.iter().map(|output_id| {
             // self.recursive_calc_node(*output_id)
-            self.recursive_calc_node(*output_id, &mut Vec::new())
+            self.recursive_calc_node(*output_id, &mut Vec::new(), trace)
         }).collect();
 
         // Reset the 'executed' flag for all nodes
@@ -153,6 +235,9 @@ impl Network {
     pub fn reset(&mut self) {
         for node in self.nodes.iter_mut() {
             node.output = 0.0;
+            if let Some(ref mut cell) = node.memory {
+                cell.reset();
+            }
             node.reset();
         }
     }
@@ -165,6 +250,28 @@ fn dependency() {
     assert_eq!(net.get_node_dependencies(5), vec![0, 1, 2, 3, 4]);
 }
 
+#[test]
+fn recurrence() {
+    let mut net = Network::new_empty(2, 1);
+    net.nodes.push(Node::new());
+    net.genome.push(Gene::random(2, 3, false));
+    assert!(!net.is_recurrent());
+    net.genome.push(Gene::random(3, 2, true));
+    assert!(!net.is_recurrent());
+    net.genome.last_mut().unwrap().enable();
+    assert!(net.is_recurrent());
+
+    // A chain this long would overflow the stack of a recursive search
+    let mut chain = Network::new_empty(1, 1);
+    chain.nodes.extend(Node::multiple_new(100000));
+    for node in 1..chain.nodes.len() - 1 {
+        chain.genome.push(Gene::random(node, node + 1, false));
+    }
+    assert!(!chain.is_recurrent());
+    chain.genome.push(Gene::random(chain.nodes.len() - 1, 2, false));
+    assert!(chain.is_recurrent());
+}
+
 #[test]
 fn persistent_results() {
     let mut net = Network::new_empty(1, 1);
@@ -182,6 +289,44 @@ fn short_term_memory() {
     assert!(res1 != res2);
 }
 
+#[test]
+fn long_term_memory() {
+    use Gate;
+    // Inputs: value, keep flag, constant bias; Output: 3; Memory cell: 4
+    let mut net = Network::new_empty(3, 1);
+    net.nodes.push(Node::new_memory());
+    net.genome = vec![
+        Gene::with_weight(0, 4, false, 1.0),
+        Gene::gate_with_weight(1, 4, Gate::Input, -100.0),
+        Gene::gate_with_weight(2, 4, Gate::Forget, 100.0),
+        Gene::with_weight(4, 3, false, 1.0)
+    ];
+    // Write a value into the memory cell once and keep it afterwards
+    let stored = net.evaluate(&vec![1.0, 0.0, 1.0]).unwrap();
+    assert!(stored[0] > 0.0);
+    for _ in 0..20 {
+        assert_eq!(net.evaluate(&vec![0.5, 1.0, 1.0]).unwrap(), stored);
+    }
+    net.reset();
+    assert_eq!(net.evaluate(&vec![0.0, 1.0, 1.0]).unwrap(), vec![0.0]);
+}
+
+#[test]
+fn traced_evaluation() {
+    let mut net = Network::new_empty(2, 1);
+    let mut untraced = net.clone();
+    let mut trace = Trace::new();
+    for _ in 0..3 {
+        assert_eq!(net.evaluate_traced(&vec![0.5, 1.0], &mut trace).unwrap(), untraced.evaluate(&vec![0.5, 1.0]).unwrap());
+    }
+    assert_eq!(trace.steps.len(), 3);
+    let step = &trace.steps[0];
+    assert_eq!(step.nodes.len(), 3);
+    assert_eq!(step.genes.len(), 2);
+    assert_eq!(step.get_node(2).unwrap().input, step.genes[0].contribution + step.genes[1].contribution);
+    assert_eq!(step.get_node(2).unwrap().output, step.outputs[0]);
+}
+
 #[test]
 fn recursive_evaluation() {
     let mut net = Network::new_empty(1, 1);
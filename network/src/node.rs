@@ -1,14 +1,25 @@
-use Float;
+#[cfg(feature = "training")]
+use rand::{thread_rng, Rng};
+use {Float, Activation, Aggregation, Gate, MemoryCell};
 
-/// Node inside a network that is just there to wrap around the sigmoid function and eventually other ones later
-#[derive(Debug, RustcDecodable, RustcEncodable, Clone, PartialEq)]
+/// Node inside a network that aggregates its inputs and wraps around an activation function (or a memory cell)
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(RustcDecodable, RustcEncodable))]
 pub struct Node {
     /// Flag to define whether or not the node has been executed (in the current 'round')
     pub executed: bool,
-    /// A list of inputs that are all summed upon evaluation
+    /// A list of inputs that are all aggregated upon evaluation
     pub inputs: Vec<Float>,
+    /// Function used to combine the inputs before the activation is applied
+    pub aggregation: Aggregation,
+    /// Constant that is added to the aggregated inputs
+    pub bias: Float,
+    /// Function applied to the biased input (unused for memory cells as they have their own squashing functions)
+    pub activation: Activation,
     /// Final output value of node after evaluate() is called
-    pub output: Float
+    pub output: Float,
+    /// Optional memory cell that turns this node into a gated memory node
+    pub memory: Option<MemoryCell>
 }
 
 impl Node {
@@ -17,7 +28,19 @@ impl Node {
         Node {
             executed: false,
             inputs: Vec::new(),
-            output: 0.0
+            aggregation: Aggregation::Sum,
+            bias: 0.0,
+            activation: Activation::Relu,
+            output: 0.0,
+            memory: None
+        }
+    }
+
+    /// Initializes a single Node instance with an attached memory cell
+    pub fn new_memory() -> Node {
+        Node {
+            memory: Some(MemoryCell::new()),
+            ..Node::new()
         }
     }
 
@@ -28,41 +51,59 @@ impl Node {
         }).collect()
     }
 
+    /// Adds a value either to the inputs or, if a gate is given, to the respective gate of the memory cell
+    ///
+    /// Values for gates of a node without a memory cell are ignored.
+    pub fn push_input(&mut self, gate: Option<Gate>, value: Float) {
+        match (gate, self.memory.as_mut()) {
+            (None, _) => self.inputs.push(value),
+            (Some(gate), Some(cell)) => cell.push(gate, value),
+            (Some(_), None) => {}
+        }
+    }
+
     /// Starts the evaluation of the node returning the result
     pub fn evaluate(&mut self) -> Float {
         if !self.executed {
-            let input_sum = self.inputs.iter().fold(0.0, |acc, input| {
-                acc + input
-            });
+            let input = self.get_input();
             self.executed = true;
             self.inputs.clear();
-            // self.output = steep_sigmoid(input_sum);
-            self.output = relu(input_sum);
+            self.output = match self.memory {
+                Some(ref mut cell) => cell.step(input),
+                None => self.activation.apply(input)
+            };
         }
         self.output
     }
 
+    /// Aggregated and biased input the node would currently be evaluated with
+    pub fn get_input(&self) -> Float {
+        self.aggregation.aggregate(&self.inputs) + self.bias
+    }
+
     /// Reset the node for a new calculation within the current instance (recurrent data is kept)
     pub fn reset(&mut self) {
         self.executed = false;
     }
-}
 
-fn relu(x: Float) -> Float {
-    if x > 0.0 { x } else { 0.0 }
-}
+    /// Replaces the aggregation function with a different, randomly chosen one
+    #[cfg(feature = "training")]
+    pub fn mutate_aggregation(&mut self) {
+        self.mutate_aggregation_with_rng(&mut thread_rng());
+    }
 
-/// Steepened sigmoid function
-fn steep_sigmoid(x: Float) -> Float {
-    1.0 / ( 1.0 + (-4.9 * x).exp())
-}
+    /// Like mutate_aggregation() but using the given generator
+    #[cfg(feature = "training")]
+    pub fn mutate_aggregation_with_rng<R: Rng>(&mut self, rng: &mut R) {
+        let current = self.aggregation;
+        while self.aggregation == current {
+            self.aggregation = Aggregation::random_with_rng(rng);
+        }
+    }
 
-#[test]
-fn sigmoid() {
-    if cfg!(feature = "single_precision") {
-        assert_eq!(steep_sigmoid(0.25), 0.77294225)
-    } else if cfg!(feature = "double_precision") {
-        assert_eq!(steep_sigmoid(0.25), 0.7729422593967386);
+    /// Whether or not this node carries a memory cell
+    pub fn is_memory(&self) -> bool {
+        self.memory.is_some()
     }
 }
 
@@ -71,3 +112,12 @@ fn evaluate_empty() {
     // Sigmoid of 0 is 0.5
     assert_eq!(Node::new().evaluate(), 0.5);
 }
+
+#[test]
+fn aggregation_before_activation() {
+    let mut node = Node::new();
+    node.aggregation = Aggregation::Min;
+    node.inputs = vec![2.0, -1.0, 3.0];
+    // relu(min(2, -1, 3)) = 0 while relu(sum) would be 4
+    assert_eq!(node.evaluate(), 0.0);
+}
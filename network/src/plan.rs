@@ -0,0 +1,68 @@
+use {
+    GID,
+    NID,
+    Network
+};
+
+/// Calculation of a single node: the listed genes are processed in order (reading the current output of their
+/// source) before the node itself is evaluated
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanStep {
+    pub node: NID,
+    pub genes: Vec<GID>
+}
+
+/// Static order in which evaluate() calculates the nodes of a network
+///
+/// Replaying the steps on the previous outputs of all nodes yields exactly the same results as the recursive
+/// evaluation (including the stale values that are read inside of cycles). Nodes that don't lead to an output are
+/// never calculated and therefore don't show up in the plan.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvaluationPlan {
+    pub steps: Vec<PlanStep>
+}
+
+impl EvaluationPlan {
+    pub fn new(net: &Network) -> EvaluationPlan {
+        let mut plan = EvaluationPlan { steps: Vec::new() };
+        let mut executed = vec![false; net.nodes.len()];
+        for output in net.outputs.iter() {
+            plan.visit(net, *output, &mut Vec::new(), &mut executed);
+        }
+        plan
+    }
+
+    /// Mirrors Network::recursive_calc_node
+    fn visit(&mut self, net: &Network, node: NID, visited: &mut Vec<NID>, executed: &mut Vec<bool>) {
+        if visited.contains(&node) || executed[node] {
+            return;
+        }
+        visited.push(node);
+
+        let genes = (0..net.genome.len()).filter(|gene| {
+            net.genome[*gene].link.1 == node && !net.genome[*gene].disabled
+        }).collect::<Vec<_>>();
+        for gene in genes.iter() {
+            self.visit(net, net.genome[*gene].link.0, visited, executed);
+        }
+        executed[node] = true;
+        self.steps.push(PlanStep { node: node, genes: genes });
+    }
+}
+
+#[test]
+fn plan_order() {
+    use Gene;
+
+    // 0 -> 2 -> 1 (output) with a cycle between 1 and 2
+    let mut net = Network::new_empty(1, 1);
+    net.nodes.push(::Node::new());
+    net.genome = vec![Gene::with_weight(0, 2, false, 1.0), Gene::with_weight(2, 1, false, 1.0), Gene::with_weight(1, 2, false, 1.0)];
+
+    let plan = EvaluationPlan::new(&net);
+    assert_eq!(plan.steps, vec![
+        PlanStep { node: 0, genes: vec![] },
+        PlanStep { node: 2, genes: vec![0, 2] },
+        PlanStep { node: 1, genes: vec![1] }
+    ]);
+}
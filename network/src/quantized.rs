@@ -0,0 +1,345 @@
+// Fixed-point inference form of a network
+//
+// Every value is an integer with a per-node amount of fraction bits (the scale is always a power of two):
+// - node outputs, raw inputs and gene weights are stored as i8
+// - the inputs of a node are converted to its accumulator format (8 more fraction bits than the int8 range of its
+//   pre-activation values), aggregated and biased in i64
+// - the activation is a 256 entry lookup table from the pre-activation (rounded to int8) to the int8 output
+//
+// Only building the network uses floating point arithmetic. Evaluating it is pure integer arithmetic, so a quantized
+// network gives the same results on every platform (and can be encoded to ship the exact tables). The tables are
+// computed with the exp() and tanh() of the platform though, so quantizing the same network on two platforms can
+// give tables that differ in the last bit. Only a given set of tables is fully deterministic.
+
+use {
+    NID,
+    Float,
+    Network,
+    Aggregation,
+    Trace,
+    EvaluationError
+};
+use plan::EvaluationPlan;
+
+/// Largest and smallest amount of fraction bits a scale can use
+const MAX_FRACTION_BITS: i8 = 24;
+/// Additional fraction bits of an accumulator compared to the pre-activation table index
+const ACCUMULATOR_BITS: i8 = 8;
+
+#[derive(Debug)]
+pub enum QuantizationError {
+    /// Memory cells can't be quantized
+    MemoryCell(NID),
+    /// At least one calibration sample is needed to determine the value ranges
+    EmptyCalibration,
+    Evaluation(EvaluationError)
+}
+
+impl From<EvaluationError> for QuantizationError {
+    fn from(error: EvaluationError) -> QuantizationError {
+        QuantizationError::Evaluation(error)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(RustcDecodable, RustcEncodable))]
+struct QuantizedGene {
+    source: NID,
+    weight: i8,
+    /// Fraction bits to drop from weight * source output to get to the accumulator format
+    shift: i8
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(RustcDecodable, RustcEncodable))]
+struct QuantizedStep {
+    node: NID,
+    genes: Vec<QuantizedGene>,
+    aggregation: Aggregation,
+    /// Bias in the accumulator format
+    bias: i64,
+    accumulator_bits: i8,
+    /// Output for every pre-activation value from -128 to 127
+    table: Vec<i8>
+}
+
+/// Network with integer arithmetic and lookup table activations created by Network::quantize()
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(RustcDecodable, RustcEncodable))]
+pub struct QuantizedNetwork {
+    inputs: usize,
+    outputs: Vec<NID>,
+    /// Fraction bits of the raw inputs
+    input_bits: Vec<i8>,
+    /// Fraction bits of the output of every node
+    output_bits: Vec<i8>,
+    steps: Vec<QuantizedStep>,
+    /// Previous output of every node
+    state: Vec<i8>,
+    max_error: Float
+}
+
+/// Most fraction bits that still allow values up to max to be stored within the limit
+fn fraction_bits(max: Float, limit: Float) -> i8 {
+    if max.is_nan() || max <= 0.0 {
+        return 7;
+    }
+    let mut bits = 0;
+    while bits < MAX_FRACTION_BITS && max * (2.0 as Float).powi(bits as i32 + 1) <= limit {
+        bits += 1;
+    }
+    while bits > -MAX_FRACTION_BITS && max * (2.0 as Float).powi(bits as i32) > limit {
+        bits -= 1;
+    }
+    bits
+}
+
+fn to_fixed(value: Float, bits: i8) -> i64 {
+    (value * (2.0 as Float).powi(bits as i32)).round() as i64
+}
+
+fn to_i8(value: i64) -> i8 {
+    value.clamp(-128, 127) as i8
+}
+
+/// Multiplies by 2^-shift, rounding half up when bits are dropped
+fn shift_round(value: i64, shift: i8) -> i64 {
+    if shift > 0 {
+        let shift = shift.min(62) as u32;
+        (value + (1 << (shift - 1))) >> shift
+    } else {
+        value.saturating_mul(1 << (-shift).min(62) as u32)
+    }
+}
+
+fn aggregate(aggregation: Aggregation, values: &mut [i64], bits: i8) -> i64 {
+    if values.is_empty() { return 0 }
+
+    let count = values.len() as i64;
+    match aggregation {
+        Aggregation::Sum => values.iter().fold(0i64, |acc, value| acc.saturating_add(*value)),
+        Aggregation::Product => values.iter().skip(1).fold(values[0], |acc, value| {
+            shift_round(acc.saturating_mul(*value), bits).clamp(i32::MIN as i64, i32::MAX as i64)
+        }),
+        Aggregation::Max => *values.iter().max().unwrap(),
+        Aggregation::Min => *values.iter().min().unwrap(),
+        Aggregation::Mean => divide_round(values.iter().fold(0i64, |acc, value| acc.saturating_add(*value)), count),
+        Aggregation::Median => {
+            values.sort();
+            let middle = values.len() / 2;
+            if values.len() % 2 == 0 {
+                divide_round(values[middle - 1] + values[middle], 2)
+            } else {
+                values[middle]
+            }
+        },
+        Aggregation::MaxAbs => values.iter().skip(1).fold(values[0], |acc, value| {
+            if value.abs() > acc.abs() { *value } else { acc }
+        })
+    }
+}
+
+/// Division rounding half away from zero
+fn divide_round(value: i64, divisor: i64) -> i64 {
+    if value >= 0 {
+        (value + divisor / 2) / divisor
+    } else {
+        (value - divisor / 2) / divisor
+    }
+}
+
+impl Network {
+    /// Converts the network into a fixed-point network
+    ///
+    /// The value ranges of all nodes are measured by evaluating the calibration samples in order, starting from a
+    /// reset copy of the network (so recurrent networks can be calibrated with sequences). The same samples are then
+    /// used to measure the maximum error of the quantized network.
+    pub fn quantize(&self, calibration: &[Vec<Float>]) -> Result<QuantizedNetwork, QuantizationError> {
+        if calibration.is_empty() {
+            return Err(QuantizationError::EmptyCalibration);
+        }
+        let plan = EvaluationPlan::new(self);
+        if let Some(step) = plan.steps.iter().find(|step| self.nodes[step.node].is_memory()) {
+            return Err(QuantizationError::MemoryCell(step.node));
+        }
+
+        // Measure the ranges of the raw inputs, pre-activation values and outputs
+        let mut copy = self.clone();
+        copy.reset();
+        let mut trace = Trace::new();
+        let mut expected = Vec::new();
+        for sample in calibration.iter() {
+            expected.push(copy.evaluate_traced(sample, &mut trace)?);
+        }
+        let mut input_max = vec![0.0 as Float; self.inputs];
+        let mut pre_activation_max = vec![0.0 as Float; self.nodes.len()];
+        let mut output_max = vec![0.0 as Float; self.nodes.len()];
+        for step in trace.steps.iter() {
+            for (max, input) in input_max.iter_mut().zip(step.inputs.iter()) {
+                *max = max.max(input.abs());
+            }
+            for node in step.nodes.iter() {
+                pre_activation_max[node.node] = pre_activation_max[node.node].max(node.input.abs());
+                output_max[node.node] = output_max[node.node].max(node.output.abs());
+            }
+        }
+
+        let input_bits = input_max.iter().map(|max| fraction_bits(*max, 127.0)).collect::<Vec<_>>();
+        let output_bits = output_max.iter().map(|max| fraction_bits(*max, 127.0)).collect::<Vec<_>>();
+        let steps = plan.steps.iter().map(|step| {
+            let node = &self.nodes[step.node];
+            let genes = step.genes.iter().map(|gene| &self.genome[*gene]).filter(|gene| gene.gate.is_none()).collect::<Vec<_>>();
+            let weight_bits = fraction_bits(genes.iter().fold(0.0 as Float, |acc, gene| acc.max(gene.weight.abs())), 127.0);
+            let pre_activation_bits = fraction_bits(pre_activation_max[step.node], 127.0);
+            let accumulator_bits = pre_activation_bits + ACCUMULATOR_BITS;
+
+            QuantizedStep {
+                node: step.node,
+                genes: genes.iter().map(|gene| QuantizedGene {
+                    source: gene.link.0,
+                    weight: to_i8(to_fixed(gene.weight, weight_bits)),
+                    shift: weight_bits + output_bits[gene.link.0] - accumulator_bits
+                }).collect(),
+                aggregation: node.aggregation,
+                bias: to_fixed(node.bias, accumulator_bits),
+                accumulator_bits: accumulator_bits,
+                table: (-128..128).map(|index| {
+                    let pre_activation = index as Float / (2.0 as Float).powi(pre_activation_bits as i32);
+                    to_i8(to_fixed(node.activation.apply(pre_activation), output_bits[step.node]))
+                }).collect()
+            }
+        }).collect();
+
+        let mut quantized = QuantizedNetwork {
+            inputs: self.inputs,
+            outputs: self.outputs.clone(),
+            input_bits: input_bits,
+            output_bits: output_bits,
+            steps: steps,
+            state: vec![0; self.nodes.len()],
+            max_error: 0.0
+        };
+        let mut max_error: Float = 0.0;
+        for (sample, expected) in calibration.iter().zip(expected.iter()) {
+            let outputs = quantized.evaluate(sample)?;
+            for (output, expected) in outputs.iter().zip(expected.iter()) {
+                max_error = max_error.max((output - expected).abs());
+            }
+        }
+        quantized.reset();
+        quantized.max_error = max_error;
+        Ok(quantized)
+    }
+}
+
+impl QuantizedNetwork {
+    /// Quantizes the inputs, evaluates the network and converts the outputs back to floating point
+    pub fn evaluate(&mut self, inputs: &Vec<Float>) -> Result<Vec<Float>, EvaluationError> {
+        if inputs.len() != self.inputs {
+            return Err(EvaluationError::InputSizeMismatch);
+        }
+        let raw = inputs.iter().zip(self.input_bits.iter()).map(|(input, bits)| to_i8(to_fixed(*input, *bits))).collect::<Vec<_>>();
+        let outputs = self.evaluate_fixed(&raw)?;
+        Ok(outputs.iter().zip(self.get_output_bits().iter()).map(|(output, bits)| {
+            *output as Float / (2.0 as Float).powi(*bits as i32)
+        }).collect())
+    }
+
+    /// Evaluates the network on inputs that already use the fraction bits of get_input_bits()
+    ///
+    /// The outputs use the fraction bits of get_output_bits().
+    pub fn evaluate_fixed(&mut self, inputs: &[i8]) -> Result<Vec<i8>, EvaluationError> {
+        if inputs.len() != self.inputs {
+            return Err(EvaluationError::InputSizeMismatch);
+        }
+        let mut values = Vec::new();
+        for step in self.steps.iter() {
+            values.clear();
+            if step.node < self.inputs {
+                values.push(shift_round(inputs[step.node] as i64, self.input_bits[step.node] - step.accumulator_bits));
+            }
+            for gene in step.genes.iter() {
+                values.push(shift_round(gene.weight as i64 * self.state[gene.source] as i64, gene.shift));
+            }
+            let pre_activation = aggregate(step.aggregation, &mut values, step.accumulator_bits).saturating_add(step.bias);
+            let index = shift_round(pre_activation, ACCUMULATOR_BITS).clamp(-128, 127);
+            self.state[step.node] = step.table[(index + 128) as usize];
+        }
+        Ok(self.outputs.iter().map(|output| self.state[*output]).collect())
+    }
+
+    /// Removes all recurrent data
+    pub fn reset(&mut self) {
+        for value in self.state.iter_mut() {
+            *value = 0;
+        }
+    }
+
+    /// Fraction bits of the inputs of evaluate_fixed()
+    pub fn get_input_bits(&self) -> &[i8] {
+        &self.input_bits
+    }
+
+    /// Fraction bits of the outputs of evaluate_fixed()
+    pub fn get_output_bits(&self) -> Vec<i8> {
+        self.outputs.iter().map(|output| self.output_bits[*output]).collect()
+    }
+
+    /// Largest absolute difference to the outputs of the original network on the calibration samples
+    pub fn get_max_error(&self) -> Float {
+        self.max_error
+    }
+}
+
+#[test]
+fn quantized_evaluation() {
+    use {NetworkBuilder, Activation};
+
+    let mut net = NetworkBuilder::new()
+        .input("a").input("b").output("y")
+        .activation("a", Activation::Identity).activation("b", Activation::Identity).activation("y", Activation::Sigmoid)
+        .hidden("h1").hidden("h2").activation("h1", Activation::Tanh).aggregation("h2", Aggregation::Max)
+        .connect("a", "h1", 1.5).connect("b", "h1", -0.75).bias("h1", 0.1)
+        .connect("a", "h2", 0.5).connect("b", "h2", 2.0)
+        .connect("h1", "y", 1.2).connect("h2", "y", -0.8).bias("y", 0.3)
+        .build().unwrap();
+    let calibration = (0..100).map(|i| vec![(i % 10) as Float / 5.0 - 1.0, (i / 10) as Float / 5.0 - 1.0]).collect::<Vec<_>>();
+
+    let mut quantized = net.quantize(&calibration).unwrap();
+    assert!(quantized.get_max_error() < 0.05, "max error {}", quantized.get_max_error());
+    for sample in calibration.iter() {
+        let expected = net.evaluate(sample).unwrap()[0];
+        assert!((quantized.evaluate(sample).unwrap()[0] - expected).abs() <= quantized.get_max_error());
+    }
+
+    // Integer evaluation is repeatable and independent of the float conversion
+    let raw = [to_i8(to_fixed(0.4, quantized.get_input_bits()[0])), to_i8(to_fixed(-0.6, quantized.get_input_bits()[1]))];
+    let first = quantized.evaluate_fixed(&raw).unwrap();
+    assert_eq!(quantized.evaluate_fixed(&raw).unwrap(), first);
+    assert_eq!(quantized.evaluate(&vec![0.4, -0.6]).unwrap()[0], first[0] as Float / (2.0 as Float).powi(quantized.get_output_bits()[0] as i32));
+}
+
+#[test]
+fn quantized_recurrence() {
+    use {NetworkBuilder, Activation};
+
+    // The output adds up its inputs over time
+    let net = NetworkBuilder::new()
+        .input("x").output("y").activation("x", Activation::Identity).activation("y", Activation::Identity)
+        .connect("x", "y", 1.0).connect("y", "y", 1.0)
+        .build().unwrap();
+    let calibration = vec![vec![0.25]; 8];
+    let mut quantized = net.quantize(&calibration).unwrap();
+    assert!(quantized.get_max_error() < 0.05);
+    let outputs = (0..4).map(|_| quantized.evaluate(&vec![0.25]).unwrap()[0]).collect::<Vec<_>>();
+    assert_eq!(outputs, vec![0.25, 0.5, 0.75, 1.0]);
+    quantized.reset();
+    assert_eq!(quantized.evaluate(&vec![0.25]).unwrap(), vec![0.25]);
+
+    assert!(net.quantize(&[]).is_err());
+    let memory = NetworkBuilder::new().input("x").output("y").memory("y").connect("x", "y", 1.0).build().unwrap();
+    match memory.quantize(&calibration) {
+        Err(QuantizationError::MemoryCell(1)) => {},
+        other => panic!("Unexpected result {:?}", other)
+    }
+}
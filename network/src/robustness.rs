@@ -0,0 +1,219 @@
+use rand::{thread_rng, Rng, XorShiftRng};
+use rand::distributions::{Normal, IndependentSample};
+
+use {
+    Float,
+    Network,
+    EvaluationError
+};
+
+/// Disturbances that are applied while evaluating a network
+///
+/// All of them are disabled by default and can be combined.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Perturbation {
+    /// Standard deviation of the Gaussian noise added to every input
+    pub input_noise: Float,
+    /// Probability of an input being replaced by zero
+    pub input_dropout: Float,
+    /// Standard deviation of the Gaussian noise added once to every enabled gene weight
+    pub weight_noise: Float
+}
+
+#[derive(Debug)]
+pub enum RobustnessError {
+    /// A field of the perturbation is out of range (noise has to be finite and non-negative, dropout a probability)
+    InvalidPerturbation(&'static str),
+    Evaluation(EvaluationError)
+}
+
+impl From<EvaluationError> for RobustnessError {
+    fn from(error: EvaluationError) -> RobustnessError {
+        RobustnessError::Evaluation(error)
+    }
+}
+
+impl Perturbation {
+    pub fn validate(&self) -> Result<(), RobustnessError> {
+        if !self.input_noise.is_finite() || self.input_noise < 0.0 {
+            return Err(RobustnessError::InvalidPerturbation("input_noise"));
+        }
+        if !self.weight_noise.is_finite() || self.weight_noise < 0.0 {
+            return Err(RobustnessError::InvalidPerturbation("weight_noise"));
+        }
+        if !(0.0..=1.0).contains(&self.input_dropout) {
+            return Err(RobustnessError::InvalidPerturbation("input_dropout"));
+        }
+        Ok(())
+    }
+}
+
+/// Copy of a network whose weights got perturbed and whose inputs are perturbed on every evaluation
+#[derive(Debug, Clone)]
+pub struct PerturbedNetwork {
+    pub network: Network,
+    perturbation: Perturbation,
+    /// Draws the input perturbations, seeded from the generator the network was created with
+    rng: XorShiftRng
+}
+
+/// Scores of a fitness function for the unperturbed network and a number of perturbed copies
+#[derive(Debug, Clone, PartialEq)]
+pub struct RobustnessReport {
+    pub baseline_score: f64,
+    pub mean_score: f64,
+    pub worst_score: f64,
+    /// Difference between the baseline and the mean score of the perturbed copies
+    pub degradation: f64
+}
+
+fn add_noise<R: Rng>(value: Float, std_dev: Float, rng: &mut R) -> Float {
+    if std_dev > 0.0 {
+        value + Normal::new(0.0, std_dev as f64).ind_sample(rng) as Float
+    } else {
+        value
+    }
+}
+
+impl PerturbedNetwork {
+    pub fn new(network: &Network, perturbation: Perturbation) -> Result<PerturbedNetwork, RobustnessError> {
+        PerturbedNetwork::new_with_rng(network, perturbation, &mut thread_rng())
+    }
+
+    /// Like new() but draws the weight perturbations and the seed for the input perturbations from the given generator
+    pub fn new_with_rng<R: Rng>(network: &Network, perturbation: Perturbation, rng: &mut R) -> Result<PerturbedNetwork, RobustnessError> {
+        perturbation.validate()?;
+        let mut network = network.clone();
+        for gene in network.genome.iter_mut().filter(|gene| !gene.disabled) {
+            gene.weight = add_noise(gene.weight, perturbation.weight_noise, rng);
+        }
+        Ok(PerturbedNetwork {
+            network: network,
+            perturbation: perturbation,
+            rng: rng.gen()
+        })
+    }
+
+    pub fn evaluate(&mut self, inputs: &Vec<Float>) -> Result<Vec<Float>, EvaluationError> {
+        let perturbation = &self.perturbation;
+        let rng = &mut self.rng;
+        let inputs = inputs.iter().map(|input| {
+            if rng.gen::<Float>() < perturbation.input_dropout {
+                0.0
+            } else {
+                add_noise(*input, perturbation.input_noise, rng)
+            }
+        }).collect();
+        self.network.evaluate(&inputs)
+    }
+
+    pub fn reset_and_evaluate(&mut self, inputs: &Vec<Float>) -> Result<Vec<Float>, EvaluationError> {
+        self.network.reset();
+        self.evaluate(inputs)
+    }
+}
+
+impl Network {
+    /// Variance of every output over the given amount of perturbed evaluations, averaged over the samples
+    ///
+    /// Every evaluation uses a freshly perturbed and reset copy of the network. Without trials the variance is zero.
+    pub fn output_variance(&self, samples: &[Vec<Float>], perturbation: &Perturbation, trials: usize) -> Result<Vec<Float>, RobustnessError> {
+        self.output_variance_with_rng(samples, perturbation, trials, &mut thread_rng())
+    }
+
+    /// Like output_variance() but perturbs the copies with the given generator
+    pub fn output_variance_with_rng<R: Rng>(&self, samples: &[Vec<Float>], perturbation: &Perturbation, trials: usize, rng: &mut R)
+        -> Result<Vec<Float>, RobustnessError>
+    {
+        perturbation.validate()?;
+        let mut variance = vec![0.0; self.outputs.len()];
+        if trials == 0 {
+            return Ok(variance);
+        }
+        for sample in samples.iter() {
+            let mut sum = vec![0.0; self.outputs.len()];
+            let mut squared_sum = vec![0.0; self.outputs.len()];
+            for _ in 0..trials {
+                let outputs = PerturbedNetwork::new_with_rng(self, perturbation.clone(), rng)?.evaluate(sample)?;
+                for (i, output) in outputs.into_iter().enumerate() {
+                    sum[i] += output;
+                    squared_sum[i] += output * output;
+                }
+            }
+            for i in 0..self.outputs.len() {
+                let mean = sum[i] / trials as Float;
+                variance[i] += (squared_sum[i] / trials as Float - mean * mean).max(0.0);
+            }
+        }
+        Ok(variance.into_iter().map(|value| value / samples.len().max(1) as Float).collect())
+    }
+
+    /// Compares the score of the network with the scores of perturbed copies
+    ///
+    /// The fitness function evaluates the network it is given like a training fitness function would. It is called
+    /// once with an unperturbed copy and then once for each trial, so it can also be used inside a training fitness
+    /// function to reward networks that don't depend on exact inputs or weights (e.g. by using the mean score).
+    pub fn robustness<F>(&self, perturbation: &Perturbation, trials: usize, fitness: F) -> Result<RobustnessReport, RobustnessError>
+        where F: FnMut(&mut PerturbedNetwork) -> f64
+    {
+        self.robustness_with_rng(perturbation, trials, fitness, &mut thread_rng())
+    }
+
+    /// Like robustness() but perturbs the copies with the given generator
+    pub fn robustness_with_rng<F, R>(&self, perturbation: &Perturbation, trials: usize, mut fitness: F, rng: &mut R)
+        -> Result<RobustnessReport, RobustnessError>
+        where F: FnMut(&mut PerturbedNetwork) -> f64, R: Rng
+    {
+        perturbation.validate()?;
+        let baseline_score = fitness(&mut PerturbedNetwork::new_with_rng(self, Perturbation::default(), rng)?);
+        let mut scores = Vec::with_capacity(trials);
+        for _ in 0..trials {
+            scores.push(fitness(&mut PerturbedNetwork::new_with_rng(self, perturbation.clone(), rng)?));
+        }
+        let mean_score = if trials > 0 { scores.iter().sum::<f64>() / trials as f64 } else { baseline_score };
+        Ok(RobustnessReport {
+            baseline_score: baseline_score,
+            mean_score: mean_score,
+            worst_score: scores.into_iter().fold(baseline_score, f64::min),
+            degradation: baseline_score - mean_score
+        })
+    }
+}
+
+#[test]
+fn perturbed_evaluation() {
+    use rand::SeedableRng;
+    use {NetworkBuilder, Activation};
+
+    let net = NetworkBuilder::new()
+        .input("x").output("y").activation("x", Activation::Identity).activation("y", Activation::Identity)
+        .connect("x", "y", 1.0)
+        .build().unwrap();
+    let samples = vec![vec![1.0], vec![-2.0]];
+
+    assert_eq!(net.output_variance(&samples, &Perturbation::default(), 10).unwrap(), vec![0.0]);
+    let noisy = Perturbation { input_noise: 0.5, ..Perturbation::default() };
+    assert!(net.output_variance(&samples, &noisy, 100).unwrap()[0] > 0.0);
+    assert_eq!(net.output_variance(&samples, &noisy, 0).unwrap(), vec![0.0]);
+    let negative = Perturbation { weight_noise: -0.1, ..Perturbation::default() };
+    match net.output_variance(&samples, &negative, 10) {
+        Err(RobustnessError::InvalidPerturbation(field)) => assert_eq!(field, "weight_noise"),
+        _ => panic!("negative noise accepted")
+    }
+    assert!(PerturbedNetwork::new(&net, Perturbation { input_noise: Float::NAN, ..Perturbation::default() }).is_err());
+
+    let dropped = Perturbation { input_dropout: 1.0, ..Perturbation::default() };
+    assert_eq!(PerturbedNetwork::new(&net, dropped).unwrap().evaluate(&vec![3.0]).unwrap(), vec![0.0]);
+
+    let fitness = |net: &mut PerturbedNetwork| -(net.reset_and_evaluate(&vec![1.0]).unwrap()[0] - 1.0).abs() as f64;
+    let report = net.robustness(&Perturbation { weight_noise: 0.5, ..Perturbation::default() }, 20, fitness).unwrap();
+    assert_eq!(report.baseline_score, 0.0);
+    assert!(report.degradation > 0.0 && report.worst_score <= report.mean_score);
+
+    let mixed = Perturbation { input_noise: 0.5, input_dropout: 0.2, weight_noise: 0.5 };
+    let seeded = || XorShiftRng::from_seed([1, 2, 3, 4]);
+    assert_eq!(net.output_variance_with_rng(&samples, &mixed, 10, &mut seeded()).unwrap(),
+               net.output_variance_with_rng(&samples, &mixed, 10, &mut seeded()).unwrap());
+    assert_eq!(net.robustness_with_rng(&mixed, 10, fitness, &mut seeded()).unwrap(),
+               net.robustness_with_rng(&mixed, 10, fitness, &mut seeded()).unwrap());
+}
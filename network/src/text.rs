@@ -0,0 +1,226 @@
+use {
+    NID,
+    Float,
+    Network,
+    NetworkBuilder,
+    BuildError,
+    Gate,
+    Activation,
+    Aggregation
+};
+
+#[derive(Debug, PartialEq)]
+pub enum ParseError {
+    /// A statement could not be understood (line number and description)
+    Syntax(usize, String),
+    /// The statements are well formed but don't describe a valid network
+    Build(BuildError)
+}
+
+impl From<BuildError> for ParseError {
+    fn from(error: BuildError) -> ParseError {
+        ParseError::Build(error)
+    }
+}
+
+/// Human readable text format for networks
+///
+/// A network is described by statements that are separated by newlines or semicolons, e.g.
+/// `in x0 x1; out y0; h3 = relu; x0 -> h3 0.53; h3 -> y0 -1.2 [disabled]`
+///
+/// * `in <names>` and `out <names>` declare the inputs and outputs
+/// * `<name> = <activation> [<aggregation>] [bias <value>] [memory]` declares a hidden node (or configures an input/output)
+/// * `<src> -> <dest>[.<gate>] <weight> [disabled]` adds a gene, optionally feeding a gate of a memory cell
+///
+/// Everything following a `#` is a comment. The printer names inputs `x<i>`, outputs `y<i>` and hidden nodes `h<NID>`
+/// and omits the default aggregation (sum) and bias (zero) so that parsing and printing round-trip exactly.
+impl Network {
+    pub fn to_text(&self) -> String {
+        let names = (0..self.nodes.len()).map(|nid| self.get_node_name(nid)).collect::<Vec<_>>();
+        let mut text = String::new();
+
+        text.push_str("in");
+        for name in names[..self.inputs].iter() {
+            text.push_str(&format!(" {}", name));
+        }
+        text.push_str("\nout");
+        for nid in self.outputs.iter() {
+            text.push_str(&format!(" {}", names[*nid]));
+        }
+        text.push('\n');
+
+        for (nid, node) in self.nodes.iter().enumerate() {
+            let is_io = nid < self.inputs || self.outputs.contains(&nid);
+            let is_default = node.activation == Activation::default() && node.aggregation == Aggregation::default()
+                && node.bias == 0.0 && !node.is_memory();
+            if is_io && is_default { continue }
+
+            text.push_str(&format!("{} = {}", names[nid], node.activation.name()));
+            if node.aggregation != Aggregation::default() {
+                text.push_str(&format!(" {}", node.aggregation.name()));
+            }
+            if node.bias != 0.0 {
+                text.push_str(&format!(" bias {:?}", node.bias));
+            }
+            if node.is_memory() {
+                text.push_str(" [memory]");
+            }
+            text.push('\n');
+        }
+
+        for gene in self.genome.iter() {
+            text.push_str(&format!("{} -> {}", names[gene.link.0], names[gene.link.1]));
+            if let Some(gate) = gene.gate {
+                text.push_str(&format!(".{}", gate.name()));
+            }
+            text.push_str(&format!(" {:?}", gene.weight));
+            if gene.disabled {
+                text.push_str(" [disabled]");
+            }
+            text.push('\n');
+        }
+
+        text
+    }
+
+    /// Parses a network from the text format described above. The resulting network has been reset.
+    pub fn from_text(text: &str) -> Result<Network, ParseError> {
+        let statements = text.lines().enumerate().flat_map(|(line_id, line)| {
+            let line = line.split('#').next().unwrap_or("");
+            line.split(';').map(|statement| {
+                (line_id + 1, statement.split_whitespace().collect::<Vec<_>>())
+            }).filter(|&(_, ref tokens)| !tokens.is_empty()).collect::<Vec<_>>()
+        }).collect::<Vec<_>>();
+
+        // Inputs and outputs are collected first to keep their NIDs in front of the hidden nodes
+        let mut builder = NetworkBuilder::new();
+        for &(_, ref tokens) in statements.iter() {
+            match tokens[0] {
+                "in" => for name in tokens[1..].iter() { builder = builder.input(name) },
+                "out" => for name in tokens[1..].iter() { builder = builder.output(name) },
+                _ => {}
+            }
+        }
+        let io_nodes = statements.iter().filter(|&&(_, ref tokens)| {
+            tokens[0] == "in" || tokens[0] == "out"
+        }).map(|&(_, ref tokens)| tokens.len() - 1).sum::<usize>();
+        // Which inputs and outputs already got a node statement
+        let mut declared_io = vec![false; io_nodes];
+
+        for &(line, ref tokens) in statements.iter() {
+            if tokens[0] == "in" || tokens[0] == "out" { continue }
+
+            if tokens.len() >= 3 && tokens[1] == "=" {
+                builder = parse_node(builder, line, tokens, &mut declared_io)?;
+            } else if tokens.len() >= 4 && tokens[1] == "->" {
+                builder = parse_gene(builder, line, tokens)?;
+            } else {
+                return Err(ParseError::Syntax(line, format!("unknown statement '{}'", tokens.join(" "))));
+            }
+        }
+
+        Ok(builder.build()?)
+    }
+
+    fn get_node_name(&self, nid: NID) -> String {
+        if nid < self.inputs {
+            format!("x{}", nid)
+        } else {
+            match self.outputs.iter().position(|output| *output == nid) {
+                Some(output_id) => format!("y{}", output_id),
+                None => format!("h{}", nid)
+            }
+        }
+    }
+}
+
+fn parse_node(mut builder: NetworkBuilder, line: usize, tokens: &Vec<&str>, declared_io: &mut Vec<bool>) -> Result<NetworkBuilder, ParseError> {
+    let name = tokens[0];
+    match builder.nid(name) {
+        Some(nid) if nid < declared_io.len() && !declared_io[nid] => declared_io[nid] = true,
+        Some(_) => return Err(ParseError::Syntax(line, format!("node '{}' is declared twice", name))),
+        None => builder = builder.hidden(name)
+    }
+
+    let activation = Activation::from_name(tokens[2]).ok_or_else(|| {
+        ParseError::Syntax(line, format!("unknown activation function '{}'", tokens[2]))
+    })?;
+    builder = builder.activation(name, activation);
+
+    let mut attributes = tokens[3..].iter();
+    while let Some(attribute) = attributes.next() {
+        builder = match *attribute {
+            "bias" => {
+                let bias = attributes.next().ok_or_else(|| ParseError::Syntax(line, "missing bias value".to_string()))?;
+                builder.bias(name, parse_float(line, bias)?)
+            },
+            "[memory]" => builder.memory(name),
+            other => match Aggregation::from_name(other) {
+                Some(aggregation) => builder.aggregation(name, aggregation),
+                None => return Err(ParseError::Syntax(line, format!("unknown node attribute '{}'", other)))
+            }
+        };
+    }
+
+    Ok(builder)
+}
+
+fn parse_gene(builder: NetworkBuilder, line: usize, tokens: &Vec<&str>) -> Result<NetworkBuilder, ParseError> {
+    let src = tokens[0];
+    let mut dest_parts = tokens[2].splitn(2, '.');
+    let dest = dest_parts.next().unwrap_or("");
+    let gate = match dest_parts.next() {
+        Some(gate) => Some(Gate::from_name(gate).ok_or_else(|| ParseError::Syntax(line, format!("unknown gate '{}'", gate)))?),
+        None => None
+    };
+    let weight = parse_float(line, tokens[3])?;
+    let disabled = tokens.len() > 4 && tokens[4] == "[disabled]";
+    if tokens.len() > 4 + disabled as usize {
+        return Err(ParseError::Syntax(line, format!("unexpected '{}' after gene", tokens[4..].join(" "))));
+    }
+
+    Ok(match (gate, disabled) {
+        (None, false) => builder.connect(src, dest, weight),
+        (None, true) => builder.connect_disabled(src, dest, weight),
+        (Some(gate), false) => builder.connect_gate(src, dest, gate, weight),
+        (Some(gate), true) => builder.connect_gate_disabled(src, dest, gate, weight)
+    })
+}
+
+fn parse_float(line: usize, token: &str) -> Result<Float, ParseError> {
+    token.parse::<Float>().map_err(|_| ParseError::Syntax(line, format!("invalid number '{}'", token)))
+}
+
+#[test]
+fn round_trip_text() {
+    let text = "in x0 x1\nout y0\nx1 = identity bias -0.25\nh3 = tanh max [memory]\nh4 = relu\n\
+                x0 -> h3 0.53\nh4 -> h3.forget 2.0\nx1 -> h4 1.0 [disabled]\nh3 -> y0 -1.2\n";
+    let net = Network::from_text(text).unwrap();
+    assert_eq!(net.to_text(), text);
+}
+
+#[test]
+fn round_trip_network() {
+    let mut net = Network::new_empty(3, 2);
+    net.genome[2].disable();
+    net.nodes[4].bias = 0.1;
+    assert_eq!(Network::from_text(&net.to_text()).unwrap(), net);
+}
+
+#[test]
+fn inline_statements() {
+    let mut net = Network::from_text("in x0 x1; out y; h1 = relu; x0 -> h1 0.5; x1 -> h1 -1 [disabled]; h1 -> y 2 # comment").unwrap();
+    assert_eq!(net.genome.len(), 3);
+    assert!(net.genome[1].disabled);
+    assert_eq!(net.evaluate(&vec![1.0, 1.0]).unwrap(), vec![1.0]);
+}
+
+#[test]
+fn parse_errors() {
+    assert_eq!(Network::from_text("in x\nout y\nx -> y one"), Err(ParseError::Syntax(3, "invalid number 'one'".to_string())));
+    assert_eq!(Network::from_text("in x\nout y\nh = swish"), Err(ParseError::Syntax(3, "unknown activation function 'swish'".to_string())));
+    assert_eq!(Network::from_text("in x; out y; h = relu; h = relu"), Err(ParseError::Syntax(1, "node 'h' is declared twice".to_string())));
+    assert_eq!(Network::from_text("in x; out y0\ny0 = relu\ny0 = tanh"), Err(ParseError::Syntax(3, "node 'y0' is declared twice".to_string())));
+    assert_eq!(Network::from_text("in x; out y; x = identity; x = relu"), Err(ParseError::Syntax(1, "node 'x' is declared twice".to_string())));
+    assert_eq!(Network::from_text("in x; out y; x -> z 1.0"), Err(ParseError::Build(BuildError::UnknownNode("z".to_string()))));
+}
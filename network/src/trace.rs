@@ -0,0 +1,103 @@
+#[cfg(feature = "serialization")]
+use rustc_serialize::json;
+
+use {
+    GID,
+    NID,
+    Float,
+    Link
+};
+
+/// Values of a single node during one evaluation step
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(RustcDecodable, RustcEncodable))]
+pub struct NodeTrace {
+    pub node: NID,
+    /// Aggregated and biased input of the node (before the activation function or memory cell)
+    pub input: Float,
+    pub output: Float
+}
+
+/// Value a single gene passed on to its target node during one evaluation step
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(RustcDecodable, RustcEncodable))]
+pub struct GeneTrace {
+    pub gene: GID,
+    pub link: Link,
+    /// Output of the source node multiplied by the weight of the gene
+    pub contribution: Float
+}
+
+/// Everything that happened inside the network during one call to evaluate
+///
+/// Nodes and genes are listed in the order they have been executed.
+/// Nodes that weren't required to calculate the outputs don't show up.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(RustcDecodable, RustcEncodable))]
+pub struct TraceStep {
+    pub inputs: Vec<Float>,
+    pub outputs: Vec<Float>,
+    pub nodes: Vec<NodeTrace>,
+    pub genes: Vec<GeneTrace>
+}
+
+impl TraceStep {
+    pub fn new(inputs: &Vec<Float>) -> TraceStep {
+        TraceStep {
+            inputs: inputs.clone(),
+            outputs: Vec::new(),
+            nodes: Vec::new(),
+            genes: Vec::new()
+        }
+    }
+
+    /// Looks up the traced values of a node
+    pub fn get_node(&self, node: NID) -> Option<&NodeTrace> {
+        self.nodes.iter().find(|trace| trace.node == node)
+    }
+
+    /// Looks up the traced contribution of a gene
+    pub fn get_gene(&self, gene: GID) -> Option<&GeneTrace> {
+        self.genes.iter().find(|trace| trace.gene == gene)
+    }
+}
+
+/// Recording of multiple consecutive evaluations of a network
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(RustcDecodable, RustcEncodable))]
+pub struct Trace {
+    pub steps: Vec<TraceStep>
+}
+
+impl Trace {
+    pub fn new() -> Trace {
+        Trace {
+            steps: Vec::new()
+        }
+    }
+
+    #[cfg(feature = "serialization")]
+    pub fn to_json(&self) -> Result<String, json::EncoderError> {
+        json::encode(self)
+    }
+
+    /// Exports the node values as CSV with the columns step, node, input and output
+    pub fn nodes_to_csv(&self) -> String {
+        self.steps.iter().enumerate().fold(String::from("step,node,input,output\n"), |mut csv, (step_id, step)| {
+            for node in step.nodes.iter() {
+                csv.push_str(&format!("{},{},{},{}\n", step_id, node.node, node.input, node.output));
+            }
+            csv
+        })
+    }
+
+    /// Exports the gene contributions as CSV with the columns step, gene, source, target and contribution
+    pub fn genes_to_csv(&self) -> String {
+        self.steps.iter().enumerate().fold(String::from("step,gene,source,target,contribution\n"), |mut csv, (step_id, step)| {
+            for gene in step.genes.iter() {
+                csv.push_str(&format!("{},{},{},{},{}\n", step_id, gene.gene, gene.link.0, gene.link.1, gene.contribution));
+            }
+            csv
+        })
+    }
+}
@@ -8,6 +8,14 @@ pub type Float = f64;
 #[cfg(feature = "single_precision")]
 pub type Float = f32;
 
+#[derive(Debug)]
+pub enum EvaluationError {
+    InputSizeMismatch,
+    /// The output or state buffers given to a frozen network don't match its size
+    BufferSizeMismatch,
+    Unknown
+}
+
 /// ID for identifying a genome uniquely across the whole population
 pub type GID = usize;
 /// ID for identifying a node. These ID's are local to a node
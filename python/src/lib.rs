@@ -0,0 +1,243 @@
+// Python bindings for neatwork (build with `maturin develop` inside this directory)
+//
+//     import pyneatwork
+//
+//     def fitness(network):
+//         cases = [([0.0, 0.0, 1.0], 0.0), ([0.0, 1.0, 1.0], 1.0), ([1.0, 0.0, 1.0], 1.0), ([1.0, 1.0, 1.0], 0.0)]
+//         error = 0.0
+//         for inputs, target in cases:
+//             network.reset()
+//             error += abs(network.evaluate(inputs)[0] - target) ** 3
+//         return -error / len(cases)
+//
+//     trainer = pyneatwork.Trainer(3, 1, fitness, population_size=15)
+//     for stats in trainer.run(100):
+//         print(stats["generation"], stats["best_score"], stats["species"])
+//     print(trainer.best_network().to_text())
+//
+//     trainer.checkpoint("run.json")  # continue later with pyneatwork.Trainer.resume("run.json", fitness)
+
+#![allow(dead_code)]
+// The code generated by pyo3 refers to ::core which is not in scope by default in the 2015 edition
+extern crate core;
+extern crate pyo3;
+extern crate neatwork;
+extern crate neat_trainer;
+
+use std::sync::{Arc, Mutex};
+
+use pyo3::prelude::*;
+use pyo3::exceptions::{PyValueError, PyRuntimeError};
+use pyo3::types::PyDict;
+
+use neatwork::{Float, Network};
+use neat_trainer::{Config, Score, Trainer, TrainingParameters, UnscoredTrainingNetwork};
+
+type FitnessFunction = Box<dyn Fn(&mut UnscoredTrainingNetwork) -> Score + Send + Sync>;
+
+/// Network that can be loaded, evaluated and exported from Python
+#[pyclass(name = "Network")]
+#[derive(Clone)]
+struct PyNetwork {
+    network: Network
+}
+
+#[pymethods]
+impl PyNetwork {
+    #[staticmethod]
+    fn from_json(data: &str) -> PyResult<PyNetwork> {
+        Network::import(data.to_string())
+            .map(|network| PyNetwork { network: network })
+            .map_err(|error| PyValueError::new_err(format!("{:?}", error)))
+    }
+
+    #[staticmethod]
+    fn from_text(text: &str) -> PyResult<PyNetwork> {
+        Network::from_text(text)
+            .map(|network| PyNetwork { network: network })
+            .map_err(|error| PyValueError::new_err(format!("{:?}", error)))
+    }
+
+    fn to_json(&self) -> PyResult<String> {
+        self.network.export().map_err(|error| PyRuntimeError::new_err(format!("{:?}", error)))
+    }
+
+    fn to_text(&self) -> String {
+        self.network.to_text()
+    }
+
+    fn to_dot(&self) -> String {
+        self.network.to_dot()
+    }
+
+    #[getter]
+    fn inputs(&self) -> usize {
+        self.network.inputs
+    }
+
+    #[getter]
+    fn outputs(&self) -> usize {
+        self.network.outputs.len()
+    }
+
+    /// Amount of enabled genes and nodes
+    fn size(&self) -> (usize, usize) {
+        self.network.get_size()
+    }
+
+    fn evaluate(&mut self, inputs: Vec<Float>) -> PyResult<Vec<Float>> {
+        self.network.evaluate(&inputs).map_err(|error| PyValueError::new_err(format!("{:?}", error)))
+    }
+
+    /// Removes all recurrent data
+    fn reset(&mut self) {
+        self.network.reset();
+    }
+}
+
+/// Parameters from keyword arguments, which use the keys (and defaults and validation) of a neat_trainer config file
+fn get_parameters(py: Python, overrides: Option<&Bound<PyDict>>) -> PyResult<TrainingParameters> {
+    let json = match overrides {
+        Some(overrides) => py.import_bound("json")?.call_method1("dumps", (overrides,))?.extract::<String>()?,
+        None => "{}".to_string()
+    };
+    Config::from_json(&json)
+        .map(|config| config.parameters)
+        .map_err(|error| PyValueError::new_err(format!("Invalid training parameters: {:?}", error)))
+}
+
+/// Evolution of a population whose fitness is calculated by a Python callable
+///
+/// The callable receives a Network and returns a float (higher is better). Keyword arguments are the keys of a
+/// neat_trainer config file, e.g. population_size=50 or speciation={"threshold": 3.0}.
+#[pyclass(name = "Trainer", unsendable)]
+struct PyTrainer {
+    trainer: Trainer<FitnessFunction>,
+    /// First exception raised by the fitness callable since the last check
+    error: Arc<Mutex<Option<PyErr>>>
+}
+
+impl PyTrainer {
+    /// Raises the exception of the fitness callable if there was one
+    fn check_error(&self) -> PyResult<()> {
+        match self.error.lock().unwrap().take() {
+            Some(error) => Err(error),
+            None => Ok(())
+        }
+    }
+
+    /// Wraps the fitness callable, so exceptions are stored instead of being lost in a worker thread
+    fn wrap_fitness(fitness: PyObject) -> (FitnessFunction, Arc<Mutex<Option<PyErr>>>) {
+        let error = Arc::new(Mutex::new(None));
+        let fitness_error = error.clone();
+        let closure: FitnessFunction = Box::new(move |net: &mut UnscoredTrainingNetwork| {
+            Python::with_gil(|py| {
+                let network = PyNetwork { network: net.network.clone() };
+                match fitness.call1(py, (network,)).and_then(|score| score.extract::<Score>(py)) {
+                    Ok(score) => score,
+                    Err(error) => {
+                        fitness_error.lock().unwrap().get_or_insert(error);
+                        Score::MIN
+                    }
+                }
+            })
+        });
+        (closure, error)
+    }
+
+    fn get_statistics(&mut self, py: Python) -> PyResult<PyObject> {
+        let best = self.trainer.get_best_network();
+        let statistics = PyDict::new_bound(py);
+        statistics.set_item("generation", self.trainer.get_generation())?;
+        statistics.set_item("species", self.trainer.species.len())?;
+        statistics.set_item("population", self.trainer.get_current_population_size())?;
+        statistics.set_item("best_score", best.score)?;
+        statistics.set_item("best_size", best.network.get_size())?;
+        Ok(statistics.into())
+    }
+}
+
+#[pymethods]
+impl PyTrainer {
+    #[new]
+    #[pyo3(signature = (inputs, outputs, fitness, **parameters))]
+    fn new(py: Python, inputs: usize, outputs: usize, fitness: PyObject, parameters: Option<&Bound<PyDict>>) -> PyResult<PyTrainer> {
+        let parameters = get_parameters(py, parameters)?;
+        let (closure, error) = PyTrainer::wrap_fitness(fitness);
+
+        // The GIL is released while the trainer runs, so the worker threads can call the fitness callable
+        let trainer = PyTrainer {
+            trainer: py.allow_threads(move || Trainer::new(parameters, inputs, outputs, closure)),
+            error: error
+        };
+        trainer.check_error()?;
+        Ok(trainer)
+    }
+
+    /// Continues a run that was saved with checkpoint()
+    #[staticmethod]
+    fn resume(path: &str, fitness: PyObject) -> PyResult<PyTrainer> {
+        let (closure, error) = PyTrainer::wrap_fitness(fitness);
+        let trainer = Trainer::resume(path, closure)
+            .map_err(|error| PyValueError::new_err(format!("Invalid checkpoint {}: {:?}", path, error)))?;
+        Ok(PyTrainer { trainer: trainer, error: error })
+    }
+
+    /// Saves the population, so the run can be continued with Trainer.resume()
+    fn checkpoint(&self, path: &str) -> PyResult<()> {
+        self.trainer.checkpoint(path).map_err(|error| PyRuntimeError::new_err(format!("Saving the checkpoint failed: {:?}", error)))
+    }
+
+    /// Advances by one generation and returns its statistics as a dict
+    fn step(&mut self, py: Python) -> PyResult<PyObject> {
+        let trainer = &mut self.trainer;
+        py.allow_threads(move || trainer.next_generation());
+        self.check_error()?;
+        self.get_statistics(py)
+    }
+
+    /// Advances by the given amount of generations and returns the statistics of each one
+    fn run(&mut self, py: Python, generations: usize) -> PyResult<Vec<PyObject>> {
+        (0..generations).map(|_| self.step(py)).collect()
+    }
+
+    #[getter]
+    fn generation(&self) -> usize {
+        self.trainer.get_generation()
+    }
+
+    fn best_network(&mut self) -> PyNetwork {
+        PyNetwork { network: self.trainer.get_best_network().network }
+    }
+
+    fn best_score(&mut self) -> Score {
+        self.trainer.get_best_network().score
+    }
+
+    /// List of dicts describing every species (size, score, top_score, staleness and the scores of its networks)
+    fn species(&self, py: Python) -> PyResult<Vec<PyObject>> {
+        self.trainer.species.iter().map(|species| {
+            let description = PyDict::new_bound(py);
+            description.set_item("size", species.networks.len())?;
+            description.set_item("score", species.score)?;
+            description.set_item("top_score", species.top_score)?;
+            description.set_item("staleness", species.staleness)?;
+            description.set_item("scores", species.networks.iter().map(|network| network.score).collect::<Vec<_>>())?;
+            Ok(description.into())
+        }).collect()
+    }
+
+    /// All networks of the population grouped by species
+    fn population(&self) -> Vec<Vec<PyNetwork>> {
+        self.trainer.species.iter().map(|species| {
+            species.networks.iter().map(|network| PyNetwork { network: network.network.clone() }).collect()
+        }).collect()
+    }
+}
+
+#[pymodule]
+fn pyneatwork(m: &Bound<PyModule>) -> PyResult<()> {
+    m.add_class::<PyNetwork>()?;
+    m.add_class::<PyTrainer>()?;
+    Ok(())
+}
@@ -0,0 +1,95 @@
+// Snapshots of a training run that can be resumed later
+//
+// A checkpoint is a JSON document with everything the trainer needs to continue: the parameters, the seed and
+// generation counter and the species with their scores, staleness and networks (including their recurrent state). Only
+// the fitness closure has to be passed in again.
+//
+// The state of the random number generator isn't saved. Every generation starts a new TrainerRng stream from the seed
+// and the generation counter, so these two already determine all random numbers of the following generations.
+//
+// The JSON parser of rustc-serialize rounds decimals slightly differently than they were printed and infinite numbers
+// are written as null, so every float is stored as a string instead (which the decoder reads with str::parse()). A
+// resumed run continues exactly like the original one would have.
+
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+use rustc_serialize::Decodable;
+use rustc_serialize::json::{Json, Decoder, DecoderError, EncoderError};
+
+use json_tree::{self, Floats};
+use species::Species;
+use trainer::TrainingParameters;
+
+/// Name of the file that automatic checkpoints are written to
+pub const CHECKPOINT_FILE_NAME: &'static str = "checkpoint.json";
+
+const VERSION: u32 = 1;
+
+#[derive(Debug)]
+pub enum CheckpointError {
+    Io(io::Error),
+    Encode(EncoderError),
+    Decode(DecoderError),
+    /// The checkpoint was written by an incompatible version of the trainer
+    UnsupportedVersion(u32)
+}
+
+impl From<io::Error> for CheckpointError {
+    fn from(error: io::Error) -> CheckpointError {
+        CheckpointError::Io(error)
+    }
+}
+
+#[derive(Debug, RustcDecodable, RustcEncodable)]
+pub struct Checkpoint {
+    pub version: u32,
+    pub parameters: TrainingParameters,
+    pub seed: u64,
+    pub generation: usize,
+    pub species: Vec<Species>
+}
+
+/// Part of a checkpoint that is read first, so the version can be checked before the rest is decoded
+#[derive(RustcDecodable)]
+struct Header {
+    version: u32
+}
+
+impl Checkpoint {
+    pub fn new(parameters: TrainingParameters, seed: u64, generation: usize, species: Vec<Species>) -> Checkpoint {
+        Checkpoint {
+            version: VERSION,
+            parameters: parameters,
+            seed: seed,
+            generation: generation,
+            species: species
+        }
+    }
+
+    /// Writes the checkpoint to a temporary file first, so an interrupted write never destroys the previous one
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), CheckpointError> {
+        let path = path.as_ref();
+        let data = json_tree::encode(self, Floats::Exact).map_err(CheckpointError::Encode)?.to_string();
+        let temporary = path.with_extension("tmp");
+        {
+            let mut file = File::create(&temporary)?;
+            file.write_all(data.as_bytes())?;
+            file.sync_all()?;
+        }
+        fs::rename(&temporary, path)?;
+        Ok(())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Checkpoint, CheckpointError> {
+        let mut data = String::new();
+        File::open(path)?.read_to_string(&mut data)?;
+        let json = Json::from_str(&data).map_err(|error| CheckpointError::Decode(DecoderError::ParseError(error)))?;
+        let header = Header::decode(&mut Decoder::new(json.clone())).map_err(CheckpointError::Decode)?;
+        if header.version != VERSION {
+            return Err(CheckpointError::UnsupportedVersion(header.version));
+        }
+        Checkpoint::decode(&mut Decoder::new(json)).map_err(CheckpointError::Decode)
+    }
+}
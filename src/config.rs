@@ -0,0 +1,322 @@
+// Training configuration that is read from and written to JSON files
+//
+// Every key is optional and falls back to its default, so a config file only has to list what differs:
+//
+//     {
+//         "population_size": 150,
+//         "speciation": { "threshold": 3.0 },
+//         "termination": { "target_score": -0.01 },
+//         "checkpoint_interval": 100
+//     }
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+use rustc_serialize::Decodable;
+use rustc_serialize::json::{self, Json, Decoder, DecoderError, ParserError};
+
+use json_tree::{self, Floats};
+use trainer::{Score, TrainingParameters};
+
+/// Name of the file that save() writes into a run directory
+pub const CONFIG_FILE_NAME: &'static str = "config.json";
+
+/// When a training run stops (it runs forever if neither is set)
+#[derive(Debug, Clone, PartialEq, RustcDecodable, RustcEncodable)]
+pub struct Termination {
+    pub max_generations: Option<usize>,
+    /// Stops as soon as the best network reaches this score
+    pub target_score: Option<Score>
+}
+
+impl Default for Termination {
+    fn default() -> Termination {
+        Termination {
+            max_generations: Some(50000),
+            target_score: None
+        }
+    }
+}
+
+impl Termination {
+    pub fn is_reached(&self, generation: usize, best_score: Score) -> bool {
+        self.max_generations.map_or(false, |max| generation >= max) || self.target_score.map_or(false, |target| best_score >= target)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Config {
+    pub parameters: TrainingParameters,
+    pub termination: Termination,
+    /// Saves a checkpoint of the run every this many generations (none are saved if it isn't set)
+    pub checkpoint_interval: Option<usize>
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Parse(ParserError),
+    /// A key has a value of the wrong type
+    Type(String, DecoderError),
+    UnknownKey(String),
+    /// A key has a value that can't be used for training
+    Invalid(String, &'static str)
+}
+
+impl From<io::Error> for ConfigError {
+    fn from(error: io::Error) -> ConfigError {
+        ConfigError::Io(error)
+    }
+}
+
+/// Keys of a JSON object that haven't been read yet
+struct Section<'a> {
+    path: &'a str,
+    object: BTreeMap<String, Json>
+}
+
+impl<'a> Section<'a> {
+    fn new(path: &'a str, json: Json) -> Result<Section<'a>, ConfigError> {
+        match json {
+            Json::Object(object) => Ok(Section { path: path, object: object }),
+            _ => Err(ConfigError::Invalid(path.to_string(), "has to be an object"))
+        }
+    }
+
+    fn key(&self, key: &str) -> String {
+        if self.path.is_empty() { key.to_string() } else { format!("{}.{}", self.path, key) }
+    }
+
+    /// Overwrites the target if the key exists
+    fn read<T: Decodable>(&mut self, key: &str, target: &mut T) -> Result<(), ConfigError> {
+        if let Some(value) = self.object.remove(key) {
+            *target = Decodable::decode(&mut Decoder::new(value)).map_err(|error| ConfigError::Type(self.key(key), error))?;
+        }
+        Ok(())
+    }
+
+    fn section(&mut self, key: &str) -> Option<Json> {
+        self.object.remove(key)
+    }
+
+    /// Fails if there are keys left that weren't read
+    fn finish(self) -> Result<(), ConfigError> {
+        match self.object.keys().next() {
+            Some(key) => Err(ConfigError::UnknownKey(self.key(key))),
+            None => Ok(())
+        }
+    }
+}
+
+impl Config {
+    /// Parses and validates a config, using the defaults for all missing keys
+    pub fn from_json(data: &str) -> Result<Config, ConfigError> {
+        let mut config = Config::default();
+        let mut root = Section::new("", Json::from_str(data).map_err(ConfigError::Parse)?)?;
+        {
+            let parameters = &mut config.parameters;
+            root.read("population_size", &mut parameters.population_size)?;
+            root.read("cull_percentage", &mut parameters.cull_percentage)?;
+            root.read("crossover_probability", &mut parameters.crossover_probability)?;
+            root.read("add_gene_probability", &mut parameters.add_gene_probability)?;
+            root.read("add_node_probability", &mut parameters.add_node_probability)?;
+            root.read("memory_cell_probability", &mut parameters.memory_cell_probability)?;
+            root.read("mutate_aggregation_probability", &mut parameters.mutate_aggregation_probability)?;
+            root.read("mutate_gene_probability", &mut parameters.mutate_gene_probability)?;
+            root.read("gene_enable_probability", &mut parameters.gene_enable_probability)?;
+            root.read("gene_disable_probability", &mut parameters.gene_disable_probability)?;
+            root.read("staleness_maximum", &mut parameters.staleness_maximum)?;
+            root.read("fitness_transform", &mut parameters.fitness_transform)?;
+            root.read("seed", &mut parameters.seed)?;
+            root.read("threads", &mut parameters.threads)?;
+
+            if let Some(json) = root.section("speciation") {
+                let mut section = Section::new("speciation", json)?;
+                section.read("disjoint_coefficient", &mut parameters.speciation.disjoint_coefficient)?;
+                section.read("weight_coefficient", &mut parameters.speciation.weight_coefficient)?;
+                section.read("threshold", &mut parameters.speciation.threshold)?;
+                section.finish()?;
+            }
+            if let Some(json) = root.section("gene_mutation") {
+                let mut section = Section::new("gene_mutation", json)?;
+                section.read("reset_probability", &mut parameters.gene_mutation.reset_probability)?;
+                section.read("strength", &mut parameters.gene_mutation.strength)?;
+                section.read("fitter_weight_probability", &mut parameters.gene_mutation.fitter_weight_probability)?;
+                section.finish()?;
+            }
+            if let Some(json) = root.section("elitism") {
+                let mut section = Section::new("elitism", json)?;
+                section.read("count", &mut parameters.elitism.count)?;
+                section.read("minimum_species_size", &mut parameters.elitism.minimum_species_size)?;
+                section.finish()?;
+            }
+            if let Some(json) = root.section("interspecies_mating") {
+                let mut section = Section::new("interspecies_mating", json)?;
+                section.read("probability", &mut parameters.interspecies_mating.probability)?;
+                section.read("species", &mut parameters.interspecies_mating.species)?;
+                section.finish()?;
+            }
+            if let Some(json) = root.section("selection") {
+                let mut section = Section::new("selection", json)?;
+                section.read("crossover", &mut parameters.selection.crossover)?;
+                section.read("mutation", &mut parameters.selection.mutation)?;
+                section.read("tournament_size", &mut parameters.selection.tournament_size)?;
+                section.finish()?;
+            }
+        }
+        if let Some(json) = root.section("termination") {
+            let mut section = Section::new("termination", json)?;
+            section.read("max_generations", &mut config.termination.max_generations)?;
+            section.read("target_score", &mut config.termination.target_score)?;
+            section.finish()?;
+        }
+        root.read("checkpoint_interval", &mut config.checkpoint_interval)?;
+        root.finish()?;
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Config, ConfigError> {
+        let mut data = String::new();
+        File::open(path)?.read_to_string(&mut data)?;
+        Config::from_json(&data)
+    }
+
+    /// Resolved config including all defaults (in the same layout that from_json() reads)
+    pub fn to_json(&self) -> String {
+        // Probabilities are printed with the precision they have (0.03 instead of 0.029999999329447753)
+        let parameters = json_tree::encode(&self.parameters, Floats::Short).expect("Encoding the parameters failed");
+        let termination = json_tree::encode(&self.termination, Floats::Short).expect("Encoding the termination failed");
+        let mut object = match parameters {
+            Json::Object(object) => object,
+            _ => unreachable!("Parameters are always encoded as an object")
+        };
+        object.insert("termination".to_string(), termination);
+        object.insert("checkpoint_interval".to_string(), self.checkpoint_interval.map_or(Json::Null, |interval| Json::U64(interval as u64)));
+        format!("{}\n", json::as_pretty_json(&Json::Object(object)))
+    }
+
+    /// Writes the resolved config into the output directory of a run
+    pub fn save<P: AsRef<Path>>(&self, directory: P) -> io::Result<()> {
+        File::create(directory.as_ref().join(CONFIG_FILE_NAME))?.write_all(self.to_json().as_bytes())
+    }
+
+    /// Rejects values that make training impossible
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let parameters = &self.parameters;
+        let probabilities = [
+            ("cull_percentage", parameters.cull_percentage),
+            ("crossover_probability", parameters.crossover_probability),
+            ("add_gene_probability", parameters.add_gene_probability),
+            ("add_node_probability", parameters.add_node_probability),
+            ("memory_cell_probability", parameters.memory_cell_probability),
+            ("mutate_aggregation_probability", parameters.mutate_aggregation_probability),
+            ("mutate_gene_probability", parameters.mutate_gene_probability),
+            ("gene_enable_probability", parameters.gene_enable_probability),
+            ("gene_disable_probability", parameters.gene_disable_probability),
+            ("gene_mutation.reset_probability", parameters.gene_mutation.reset_probability),
+            ("gene_mutation.fitter_weight_probability", parameters.gene_mutation.fitter_weight_probability),
+            ("interspecies_mating.probability", parameters.interspecies_mating.probability)
+        ];
+        for &(key, probability) in probabilities.iter() {
+            if !(0.0..=1.0).contains(&probability) {
+                return Err(ConfigError::Invalid(key.to_string(), "has to be a probability between 0 and 1"));
+            }
+        }
+
+        // Every species keeps at least one network, so the initial species needs room to breed
+        if parameters.population_size < 2 {
+            return Err(ConfigError::Invalid("population_size".to_string(), "has to be at least 2"));
+        }
+        // No species can be larger than the population, and keeping all of it as elites leaves no room for offspring
+        if parameters.elitism.count >= parameters.population_size {
+            return Err(ConfigError::Invalid("elitism.count".to_string(), "has to be smaller than the population size"));
+        }
+        if parameters.elitism.minimum_species_size > parameters.population_size {
+            return Err(ConfigError::Invalid("elitism.minimum_species_size".to_string(), "can't be larger than the population size"));
+        }
+        let coefficients = [
+            ("speciation.disjoint_coefficient", parameters.speciation.disjoint_coefficient),
+            ("speciation.weight_coefficient", parameters.speciation.weight_coefficient),
+            ("gene_mutation.strength", parameters.gene_mutation.strength as f64)
+        ];
+        for &(key, value) in coefficients.iter() {
+            if !value.is_finite() || value < 0.0 {
+                return Err(ConfigError::Invalid(key.to_string(), "has to be a finite, non-negative number"));
+            }
+        }
+        if parameters.selection.tournament_size == 0 {
+            return Err(ConfigError::Invalid("selection.tournament_size".to_string(), "has to be at least 1"));
+        }
+        if parameters.speciation.threshold.is_nan() || parameters.speciation.threshold <= 0.0 {
+            return Err(ConfigError::Invalid("speciation.threshold".to_string(), "has to be positive"));
+        }
+        if self.termination.max_generations == Some(0) {
+            return Err(ConfigError::Invalid("termination.max_generations".to_string(), "has to be at least 1"));
+        }
+        if self.checkpoint_interval == Some(0) {
+            return Err(ConfigError::Invalid("checkpoint_interval".to_string(), "has to be at least 1"));
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn config_defaults() {
+    use fitness::FitnessTransform;
+    use selection::Selection;
+
+    let config = Config::from_json(r#"{ "population_size": 150, "speciation": { "threshold": 3.0 }, "termination": { "target_score": -0.01 }, "checkpoint_interval": 100 }"#).unwrap();
+    assert_eq!(config.parameters.population_size, 150);
+    assert_eq!(config.parameters.speciation.threshold, 3.0);
+    assert_eq!(config.parameters.speciation.disjoint_coefficient, 1.0);
+    assert_eq!(config.parameters.cull_percentage, TrainingParameters::default().cull_percentage);
+    assert_eq!(config.termination, Termination { max_generations: Some(50000), target_score: Some(-0.01) });
+    assert_eq!(config.checkpoint_interval, Some(100));
+    assert_eq!(Config::from_json(r#"{ "fitness_transform": "Rank" }"#).unwrap().parameters.fitness_transform, FitnessTransform::Rank);
+    let selection = Config::from_json(r#"{ "selection": { "crossover": "Tournament", "tournament_size": 5 } }"#).unwrap().parameters.selection;
+    assert_eq!((selection.crossover, selection.mutation, selection.tournament_size), (Selection::Tournament, Selection::Uniform, 5));
+
+    // The resolved config reads back to the same values
+    assert_eq!(Config::from_json(&config.to_json()).unwrap(), config);
+    assert!(config.to_json().contains("\"add_gene_probability\": 0.03,"));
+    assert_eq!(Config::from_json("{}").unwrap(), Config::default());
+}
+
+#[test]
+fn config_validation() {
+    match Config::from_json(r#"{ "crossover_probability": 1.5 }"#) {
+        Err(ConfigError::Invalid(key, _)) => assert_eq!(key, "crossover_probability"),
+        other => panic!("{:?}", other)
+    }
+    match Config::from_json(r#"{ "gene_mutation": { "strenght": 0.5 } }"#) {
+        Err(ConfigError::UnknownKey(key)) => assert_eq!(key, "gene_mutation.strenght"),
+        other => panic!("{:?}", other)
+    }
+    match Config::from_json(r#"{ "fitness_transform": "Median" }"#) {
+        Err(ConfigError::Type(key, _)) => assert_eq!(key, "fitness_transform"),
+        other => panic!("{:?}", other)
+    }
+    match Config::from_json(r#"{ "population_size": "many" }"#) {
+        Err(ConfigError::Type(key, _)) => assert_eq!(key, "population_size"),
+        other => panic!("{:?}", other)
+    }
+    assert!(Config::from_json(r#"{ "population_size": 1 }"#).is_err());
+    assert!(Config::from_json(r#"{ "speciation": { "threshold": 0 } }"#).is_err());
+    assert!(Config::from_json(r#"{ "checkpoint_interval": 0 }"#).is_err());
+    assert!(Config::from_json(r#"{ "selection": { "tournament_size": 0 } }"#).is_err());
+    assert!(Config::from_json(r#"{ "interspecies_mating": { "probability": -0.1 } }"#).is_err());
+    match Config::from_json(r#"{ "population_size": 10, "elitism": { "minimum_species_size": 11 } }"#) {
+        Err(ConfigError::Invalid(key, _)) => assert_eq!(key, "elitism.minimum_species_size"),
+        other => panic!("{:?}", other)
+    }
+    match Config::from_json(r#"{ "population_size": 3, "elitism": { "count": 3 } }"#) {
+        Err(ConfigError::Invalid(key, _)) => assert_eq!(key, "elitism.count"),
+        other => panic!("{:?}", other)
+    }
+    assert!(Config::from_json(r#"{ "population_size": 10, "elitism": { "count": 2, "minimum_species_size": 10 } }"#).is_ok());
+    assert!(Config::from_json("[]").is_err());
+}
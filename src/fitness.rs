@@ -0,0 +1,73 @@
+// Transforms from the scores of the fitness closure to the fitness that selection and offspring allocation work with
+//
+// All transforms keep the order of the networks, so culling and finding the best network work on the raw scores.
+// What changes is how much better a network has to be to get more offspring.
+
+use trainer::Score;
+
+#[derive(Debug, Clone, Copy, PartialEq, RustcDecodable, RustcEncodable)]
+pub enum FitnessTransform {
+    /// Scores are used as they are, so negative ones count as zero
+    Raw,
+    /// Scores minus the lowest score of the population
+    Shifted,
+    /// Position in the population sorted by score (1 for the worst network, equal scores share their average rank)
+    Rank,
+    /// Distance from the average score in standard deviations, so networks below the average count as zero
+    ZScore
+}
+
+impl Default for FitnessTransform {
+    fn default() -> FitnessTransform {
+        FitnessTransform::Shifted
+    }
+}
+
+impl FitnessTransform {
+    /// Fitness of every score of the population (scores that aren't numbers count as the lowest ones)
+    pub fn apply(&self, scores: &[Score]) -> Vec<Score> {
+        let scores = scores.iter().map(|&score| if score.is_nan() { Score::NEG_INFINITY } else { score }).collect::<Vec<_>>();
+        let finite = scores.iter().cloned().filter(|score| score.is_finite()).collect::<Vec<_>>();
+        match *self {
+            FitnessTransform::Raw => scores.iter().map(|&score| score.max(0.0)).collect(),
+            FitnessTransform::Shifted => {
+                let minimum = finite.iter().fold(Score::INFINITY, |acc, &score| acc.min(score));
+                scores.iter().map(|&score| (score - minimum).max(0.0)).collect()
+            },
+            FitnessTransform::Rank => {
+                let mut order = (0..scores.len()).collect::<Vec<_>>();
+                order.sort_by(|&a, &b| scores[a].partial_cmp(&scores[b]).expect("Score comparison failed"));
+                let mut ranks = vec![0.0; scores.len()];
+                let mut start = 0;
+                while start < order.len() {
+                    let end = start + order[start..].iter().take_while(|&&index| scores[index] == scores[order[start]]).count();
+                    let rank = (start + end + 1) as Score / 2.0;
+                    for &index in order[start..end].iter() {
+                        ranks[index] = rank;
+                    }
+                    start = end;
+                }
+                ranks
+            },
+            FitnessTransform::ZScore => {
+                let count = finite.len() as Score;
+                let mean = finite.iter().sum::<Score>() / count;
+                let deviation = (finite.iter().map(|score| (score - mean).powi(2)).sum::<Score>() / count).sqrt();
+                scores.iter().map(|&score| {
+                    if deviation > 0.0 && deviation.is_finite() { ((score - mean) / deviation).max(0.0) } else { 0.0 }
+                }).collect()
+            }
+        }
+    }
+}
+
+#[test]
+fn fitness_transforms() {
+    let scores = [-2.0, 1.0, -2.0, 3.0, Score::NAN];
+    assert_eq!(FitnessTransform::Raw.apply(&scores), vec![0.0, 1.0, 0.0, 3.0, 0.0]);
+    assert_eq!(FitnessTransform::Shifted.apply(&scores), vec![0.0, 3.0, 0.0, 5.0, 0.0]);
+    assert_eq!(FitnessTransform::Rank.apply(&scores), vec![2.5, 4.0, 2.5, 5.0, 1.0]);
+    let z = FitnessTransform::ZScore.apply(&[1.0, 3.0]);
+    assert_eq!(z, vec![0.0, 1.0]);
+    assert_eq!(FitnessTransform::ZScore.apply(&[2.0, 2.0]), vec![0.0, 0.0]);
+}
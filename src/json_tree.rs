@@ -0,0 +1,217 @@
+// Encoder that builds a Json tree instead of writing text, so the floats can be stored differently than json::encode()
+// would write them
+//
+// The tree has the same layout as the text of json::encode(), so json::decode() reads it back.
+
+use std::collections::BTreeMap;
+use std::mem;
+
+use rustc_serialize::{Encodable, Encoder};
+use rustc_serialize::json::{Json, EncoderError};
+
+/// How floats are stored in the tree
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Floats {
+    /// Strings of their exact value, which json::Decoder reads with str::parse() (its own parser rounds slightly
+    /// differently and infinite numbers would be written as null)
+    Exact,
+    /// Numbers, f32 with the digits they were written with (0.03 instead of 0.029999999329447753)
+    Short
+}
+
+/// Encodes the object into a Json tree
+pub fn encode<T: Encodable>(object: &T, floats: Floats) -> Result<Json, EncoderError> {
+    let mut encoder = TreeEncoder { value: Json::Null, key: None, floats: floats };
+    object.encode(&mut encoder)?;
+    Ok(encoder.value)
+}
+
+struct TreeEncoder {
+    value: Json,
+    key: Option<String>,
+    floats: Floats
+}
+
+type EncodeResult = Result<(), EncoderError>;
+
+impl TreeEncoder {
+    /// Encodes a child value without replacing the current one
+    fn child<F>(&mut self, f: F) -> Result<Json, EncoderError> where F: FnOnce(&mut Self) -> EncodeResult {
+        let parent = mem::replace(&mut self.value, Json::Null);
+        f(self)?;
+        Ok(mem::replace(&mut self.value, parent))
+    }
+
+    fn push<F>(&mut self, f: F) -> EncodeResult where F: FnOnce(&mut Self) -> EncodeResult {
+        let element = self.child(f)?;
+        if let Json::Array(ref mut array) = self.value {
+            array.push(element);
+        }
+        Ok(())
+    }
+
+    fn insert(&mut self, key: String, value: Json) {
+        if let Json::Object(ref mut object) = self.value {
+            object.insert(key, value);
+        }
+    }
+}
+
+impl Encoder for TreeEncoder {
+    type Error = EncoderError;
+
+    fn emit_nil(&mut self) -> EncodeResult { self.value = Json::Null; Ok(()) }
+    fn emit_usize(&mut self, v: usize) -> EncodeResult { self.emit_u64(v as u64) }
+    fn emit_u64(&mut self, v: u64) -> EncodeResult { self.value = Json::U64(v); Ok(()) }
+    fn emit_u32(&mut self, v: u32) -> EncodeResult { self.emit_u64(v as u64) }
+    fn emit_u16(&mut self, v: u16) -> EncodeResult { self.emit_u64(v as u64) }
+    fn emit_u8(&mut self, v: u8) -> EncodeResult { self.emit_u64(v as u64) }
+    fn emit_isize(&mut self, v: isize) -> EncodeResult { self.emit_i64(v as i64) }
+    fn emit_i64(&mut self, v: i64) -> EncodeResult { self.value = Json::I64(v); Ok(()) }
+    fn emit_i32(&mut self, v: i32) -> EncodeResult { self.emit_i64(v as i64) }
+    fn emit_i16(&mut self, v: i16) -> EncodeResult { self.emit_i64(v as i64) }
+    fn emit_i8(&mut self, v: i8) -> EncodeResult { self.emit_i64(v as i64) }
+    fn emit_bool(&mut self, v: bool) -> EncodeResult { self.value = Json::Boolean(v); Ok(()) }
+
+    fn emit_f64(&mut self, v: f64) -> EncodeResult {
+        self.value = match self.floats {
+            Floats::Exact => Json::String(v.to_string()),
+            Floats::Short => Json::F64(v)
+        };
+        Ok(())
+    }
+
+    fn emit_f32(&mut self, v: f32) -> EncodeResult {
+        match self.floats {
+            // Every f32 is exactly representable as f64, so it survives the detour
+            Floats::Exact => self.emit_f64(v as f64),
+            Floats::Short => self.emit_f64(v.to_string().parse().expect("Printed floats can be parsed"))
+        }
+    }
+
+    fn emit_char(&mut self, v: char) -> EncodeResult { self.value = Json::String(v.to_string()); Ok(()) }
+    fn emit_str(&mut self, v: &str) -> EncodeResult { self.value = Json::String(v.to_string()); Ok(()) }
+
+    fn emit_enum<F>(&mut self, _name: &str, f: F) -> EncodeResult where F: FnOnce(&mut Self) -> EncodeResult {
+        f(self)
+    }
+
+    fn emit_enum_variant<F>(&mut self, name: &str, _id: usize, len: usize, f: F) -> EncodeResult
+        where F: FnOnce(&mut Self) -> EncodeResult
+    {
+        if len == 0 {
+            return self.emit_str(name);
+        }
+        let fields = self.child(|encoder| {
+            encoder.value = Json::Array(Vec::new());
+            f(encoder)
+        })?;
+        self.value = Json::Object(BTreeMap::new());
+        self.insert("variant".to_string(), Json::String(name.to_string()));
+        self.insert("fields".to_string(), fields);
+        Ok(())
+    }
+
+    fn emit_enum_variant_arg<F>(&mut self, _idx: usize, f: F) -> EncodeResult where F: FnOnce(&mut Self) -> EncodeResult {
+        self.push(f)
+    }
+
+    fn emit_enum_struct_variant<F>(&mut self, name: &str, id: usize, len: usize, f: F) -> EncodeResult
+        where F: FnOnce(&mut Self) -> EncodeResult
+    {
+        self.emit_enum_variant(name, id, len, f)
+    }
+
+    fn emit_enum_struct_variant_field<F>(&mut self, _name: &str, idx: usize, f: F) -> EncodeResult
+        where F: FnOnce(&mut Self) -> EncodeResult
+    {
+        self.emit_enum_variant_arg(idx, f)
+    }
+
+    fn emit_struct<F>(&mut self, _name: &str, _len: usize, f: F) -> EncodeResult where F: FnOnce(&mut Self) -> EncodeResult {
+        self.value = Json::Object(BTreeMap::new());
+        f(self)
+    }
+
+    fn emit_struct_field<F>(&mut self, name: &str, _idx: usize, f: F) -> EncodeResult where F: FnOnce(&mut Self) -> EncodeResult {
+        let value = self.child(f)?;
+        self.insert(name.to_string(), value);
+        Ok(())
+    }
+
+    fn emit_tuple<F>(&mut self, len: usize, f: F) -> EncodeResult where F: FnOnce(&mut Self) -> EncodeResult {
+        self.emit_seq(len, f)
+    }
+
+    fn emit_tuple_arg<F>(&mut self, idx: usize, f: F) -> EncodeResult where F: FnOnce(&mut Self) -> EncodeResult {
+        self.emit_seq_elt(idx, f)
+    }
+
+    fn emit_tuple_struct<F>(&mut self, _name: &str, len: usize, f: F) -> EncodeResult where F: FnOnce(&mut Self) -> EncodeResult {
+        self.emit_seq(len, f)
+    }
+
+    fn emit_tuple_struct_arg<F>(&mut self, idx: usize, f: F) -> EncodeResult where F: FnOnce(&mut Self) -> EncodeResult {
+        self.emit_seq_elt(idx, f)
+    }
+
+    fn emit_option<F>(&mut self, f: F) -> EncodeResult where F: FnOnce(&mut Self) -> EncodeResult {
+        f(self)
+    }
+
+    fn emit_option_none(&mut self) -> EncodeResult {
+        self.emit_nil()
+    }
+
+    fn emit_option_some<F>(&mut self, f: F) -> EncodeResult where F: FnOnce(&mut Self) -> EncodeResult {
+        f(self)
+    }
+
+    fn emit_seq<F>(&mut self, _len: usize, f: F) -> EncodeResult where F: FnOnce(&mut Self) -> EncodeResult {
+        self.value = Json::Array(Vec::new());
+        f(self)
+    }
+
+    fn emit_seq_elt<F>(&mut self, _idx: usize, f: F) -> EncodeResult where F: FnOnce(&mut Self) -> EncodeResult {
+        self.push(f)
+    }
+
+    fn emit_map<F>(&mut self, _len: usize, f: F) -> EncodeResult where F: FnOnce(&mut Self) -> EncodeResult {
+        self.value = Json::Object(BTreeMap::new());
+        f(self)
+    }
+
+    // JSON keys have to be strings, which the decoder parses back into numbers where needed
+    fn emit_map_elt_key<F>(&mut self, _idx: usize, f: F) -> EncodeResult where F: FnOnce(&mut Self) -> EncodeResult {
+        self.key = Some(match self.child(f)? {
+            Json::String(key) => key,
+            key => key.to_string()
+        });
+        Ok(())
+    }
+
+    fn emit_map_elt_val<F>(&mut self, _idx: usize, f: F) -> EncodeResult where F: FnOnce(&mut Self) -> EncodeResult {
+        let value = self.child(f)?;
+        let key = self.key.take().unwrap_or_default();
+        self.insert(key, value);
+        Ok(())
+    }
+}
+
+#[test]
+fn exact_floats() {
+    use rustc_serialize::json;
+
+    // Including subnormals and negative zero, which are compared by their bits
+    let values = vec![0.5428319201698241, -0.2522486165836808, 1e-7, f64::NEG_INFINITY, f64::INFINITY, f64::MIN_POSITIVE,
+                      5e-324, -2.2250738585072e-309, -0.0, f64::MAX];
+    let singles = vec![0.1f32, f32::NEG_INFINITY, f32::INFINITY, 1e-45, -1.1754942e-38, f32::MAX];
+    let data = encode(&(values.clone(), singles.clone(), Some(-3i64)), Floats::Exact).unwrap().to_string();
+    let decoded: (Vec<f64>, Vec<f32>, Option<i64>) = json::decode(&data).unwrap();
+    assert_eq!(decoded.0.iter().map(|value| value.to_bits()).collect::<Vec<_>>(), values.iter().map(|value| value.to_bits()).collect::<Vec<_>>());
+    assert_eq!(decoded.1.iter().map(|value| value.to_bits()).collect::<Vec<_>>(), singles.iter().map(|value| value.to_bits()).collect::<Vec<_>>());
+    assert_eq!(decoded.2, Some(-3));
+
+    let nan: (f64, f32) = json::decode(&encode(&(f64::NAN, f32::NAN), Floats::Exact).unwrap().to_string()).unwrap();
+    assert!(nan.0.is_nan() && nan.1.is_nan());
+}
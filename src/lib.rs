@@ -0,0 +1,59 @@
+//! Evolution of neatwork networks with the NEAT algorithm
+//!
+//! A Trainer is created from a set of TrainingParameters and a fitness closure that scores a network (higher is
+//! better). Every step of the trainer (or call to next_generation()) breeds a new generation, and the population can
+//! be inspected through the species it is divided into. Networks are scored in parallel, so the closure has to be
+//! Send and Sync. A run can be saved with Trainer::checkpoint() and continued with Trainer::resume().
+//!
+//! ```
+//! use neat_trainer::{Trainer, TrainingParameters, UnscoredTrainingNetwork};
+//!
+//! // The closer the output for the input 1 is to 0.5, the better
+//! let fitness = |net: &mut UnscoredTrainingNetwork| {
+//!     match net.reset_and_evaluate(&vec![1.0]) {
+//!         Ok(outputs) => -(outputs[0] as f64 - 0.5).abs(),
+//!         Err(_) => -1.0
+//!     }
+//! };
+//! let parameters = TrainingParameters { population_size: 20, seed: Some(1), ..TrainingParameters::default() };
+//! let mut trainer = Trainer::new(parameters, 1, 1, fitness);
+//! for _ in 0..10 {
+//!     trainer.next_generation();
+//! }
+//! let best = trainer.get_best_network();
+//! assert!(best.score <= 0.0);
+//! ```
+
+extern crate neatwork;
+extern crate rand;
+extern crate rustc_serialize;
+extern crate num_cpus;
+
+mod rng;
+pub use rng::TrainerRng;
+
+mod fitness;
+pub use fitness::FitnessTransform;
+
+mod selection;
+pub use selection::{SelectionStrategy, Selection, SelectionParameters, Tournament, FitnessProportional, RankProportional, Uniform};
+
+mod species;
+pub use species::Species;
+
+mod trainer;
+pub use trainer::{Trainer, TrainingParameters, SpeciationParameters, GeneMutationParameters, ElitismParameters, InterspeciesParameters, Score, Probability};
+
+mod training_network;
+pub use training_network::{ScoredTrainingNetwork, UnscoredTrainingNetwork, CrossoverError};
+
+mod json_tree;
+
+mod checkpoint;
+pub use checkpoint::{Checkpoint, CheckpointError, CHECKPOINT_FILE_NAME};
+
+mod config;
+pub use config::{Config, ConfigError, Termination, CONFIG_FILE_NAME};
+
+mod tasks;
+pub use tasks::Task;
@@ -1,128 +1,339 @@
-#![allow(dead_code)]
+// Command-line interface of the trainer
+//
+//     neat_trainer train <task> [--config FILE] [--output DIR] [--resume CHECKPOINT]
+//     neat_trainer eval <network> [--csv FILE] [--reset]
+//     neat_trainer inspect <network>
+//     neat_trainer render <network> [--format dot|svg] [--output FILE]
+//
+// Networks are read from JSON (as written by train) or from the text format. Results go to stdout in a
+// machine-readable form (tab separated progress, comma separated outputs, JSON statistics) and diagnostics to stderr.
+
 extern crate neatwork;
+extern crate neat_trainer;
+extern crate rustc_serialize;
 extern crate rand;
 
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::Path;
+use std::process::{self, Command, Stdio};
+use std::env;
+
 use rand::{thread_rng, Rng};
+use rustc_serialize::json::{self, Json, ToJson};
 
-mod species;
-mod trainer;
-mod training_network;
+use neatwork::{Float, Network, Activation, Aggregation};
+use neat_trainer::{Config, ConfigError, Task, Trainer, UnscoredTrainingNetwork, CHECKPOINT_FILE_NAME};
 
-use trainer::{TrainingParameters, Trainer};
+const USAGE: &'static str = "Usage:
+    neat_trainer train <task> [--config FILE] [--output DIR] [--resume CHECKPOINT]
+    neat_trainer eval <network> [--csv FILE] [--reset]
+    neat_trainer inspect <network>
+    neat_trainer render <network> [--format dot|svg] [--output FILE]";
 
-fn main() {
-    println!("Hello world!");
-    let runs = 4;
-    let parameters = TrainingParameters {
-        population_size: 15,
-        cull_percentage: 0.5,
-        crossover_probability: 0.75,
-        add_gene_probability: 0.03,
-        add_node_probability: 0.05,
-        mutate_gene_probability: 0.9,
-        gene_enable_probability: 0.4,
-        gene_disable_probability: 0.2,
-        staleness_maximum: 15
+/// Name of the file that train writes the best network into
+const BEST_NETWORK_FILE_NAME: &'static str = "best.json";
+
+#[derive(Debug)]
+enum CliError {
+    /// Invalid command line arguments (exit code 2)
+    Usage(String),
+    /// Invalid config file (exit code 3)
+    Config(ConfigError),
+    /// The training ended without reaching the target score of the config (exit code 4)
+    TargetMissed,
+    /// Anything else that went wrong (exit code 1)
+    Failure(String)
+}
+
+impl CliError {
+    fn exit_code(&self) -> i32 {
+        match *self {
+            CliError::Failure(_) => 1,
+            CliError::Usage(_) => 2,
+            CliError::Config(_) => 3,
+            CliError::TargetMissed => 4
+        }
+    }
+}
+
+impl From<io::Error> for CliError {
+    fn from(error: io::Error) -> CliError {
+        CliError::Failure(error.to_string())
+    }
+}
+
+/// Positional arguments and --flags of a subcommand
+#[derive(Debug, PartialEq)]
+struct Arguments {
+    positional: Vec<String>,
+    options: BTreeMap<String, Option<String>>
+}
+
+impl Arguments {
+    /// Splits the arguments, where valued options take the next argument and switches don't
+    fn parse(arguments: &[String], valued: &[&str], switches: &[&str]) -> Result<Arguments, CliError> {
+        let mut result = Arguments { positional: Vec::new(), options: BTreeMap::new() };
+        let mut arguments = arguments.iter();
+        while let Some(argument) = arguments.next() {
+            if !argument.starts_with("--") {
+                result.positional.push(argument.clone());
+                continue;
+            }
+            let name = &argument[2..];
+            if valued.contains(&name) {
+                let value = arguments.next().ok_or_else(|| CliError::Usage(format!("--{} requires a value", name)))?;
+                result.options.insert(name.to_string(), Some(value.clone()));
+            } else if switches.contains(&name) {
+                result.options.insert(name.to_string(), None);
+            } else {
+                return Err(CliError::Usage(format!("Unknown option {}", argument)));
+            }
+        }
+        Ok(result)
+    }
+
+    /// The single positional argument of the subcommand
+    fn get_target(&self, name: &str) -> Result<&str, CliError> {
+        match self.positional.len() {
+            1 => Ok(&self.positional[0]),
+            0 => Err(CliError::Usage(format!("Missing <{}>", name))),
+            _ => Err(CliError::Usage(format!("Unexpected argument {}", self.positional[1])))
+        }
+    }
+
+    fn get(&self, name: &str) -> Option<&str> {
+        self.options.get(name).and_then(|value| value.as_ref()).map(|value| value.as_str())
+    }
+
+    fn has(&self, name: &str) -> bool {
+        self.options.contains_key(name)
+    }
+}
+
+fn load_network(path: &str) -> Result<Network, CliError> {
+    let mut data = String::new();
+    File::open(path)?.read_to_string(&mut data)?;
+    if data.trim_start().starts_with('{') {
+        Network::import(data).map_err(|error| CliError::Failure(format!("Invalid network {}: {:?}", path, error)))
+    } else {
+        Network::from_text(&data).map_err(|error| CliError::Failure(format!("Invalid network {}: {:?}", path, error)))
+    }
+}
+
+fn train(arguments: &Arguments) -> Result<(), CliError> {
+    let name = arguments.get_target("task")?;
+    let task = Task::from_name(name).ok_or_else(|| {
+        let names = Task::all().iter().map(|task| task.name()).collect::<Vec<_>>();
+        CliError::Usage(format!("Unknown task {} (available: {})", name, names.join(", ")))
+    })?;
+    let mut config = match arguments.get("config") {
+        Some(path) => Config::load(path).map_err(CliError::Config)?,
+        None => Config::default()
+    };
+    // The resolved config always contains a seed, so every run can be reproduced
+    if config.parameters.seed.is_none() {
+        config.parameters.seed = Some(thread_rng().gen());
+    }
+    let fitness = |net: &mut UnscoredTrainingNetwork| task.fitness(net);
+    let mut trainer = match arguments.get("resume") {
+        // A resumed run keeps the parameters of the checkpoint, only the termination and checkpointing come from the config
+        Some(path) => {
+            let trainer = Trainer::resume(path, fitness)
+                .map_err(|error| CliError::Failure(format!("Invalid checkpoint {}: {:?}", path, error)))?;
+            config.parameters = trainer.get_parameters().clone();
+            trainer
+        },
+        None => Trainer::new(config.parameters.clone(), task.inputs(), task.outputs(), fitness)
+    };
+    let output = Path::new(arguments.get("output").unwrap_or("output"));
+    fs::create_dir_all(output)?;
+    config.save(output)?;
+    let checkpoint = |trainer: &Trainer<_>| {
+        trainer.checkpoint(output.join(CHECKPOINT_FILE_NAME))
+            .map_err(|error| CliError::Failure(format!("Saving the checkpoint failed: {:?}", error)))
+    };
+
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    writeln!(stdout, "generation\tbest_score\tspecies\tpopulation\tgenes\tnodes")?;
+    // The best network is written whenever it improves, so runs without a generation limit still leave a result behind
+    let mut best_score = None;
+    loop {
+        trainer.next_generation();
+        let best = trainer.get_best_network();
+        let size = best.network.get_size();
+        writeln!(stdout, "{}\t{}\t{}\t{}\t{}\t{}", trainer.get_generation(), best.score, trainer.species.len(),
+                 trainer.get_current_population_size(), size.0, size.1)?;
+
+        let improved = match best_score {
+            Some(score) => best.score > score,
+            None => true
+        };
+        if improved {
+            best_score = Some(best.score);
+            let json = best.network.export().map_err(|error| CliError::Failure(format!("{:?}", error)))?;
+            File::create(output.join(BEST_NETWORK_FILE_NAME))?.write_all(json.as_bytes())?;
+        }
+
+        let finished = config.termination.is_reached(trainer.get_generation(), best.score);
+        if let Some(interval) = config.checkpoint_interval {
+            if finished || trainer.get_generation() % interval == 0 {
+                checkpoint(&trainer)?;
+            }
+        }
+        if finished {
+            return match config.termination.target_score {
+                Some(target) if best.score < target => Err(CliError::TargetMissed),
+                _ => Ok(())
+            };
+        }
+    }
+}
+
+/// Parses a line of comma and/or whitespace separated numbers
+fn parse_inputs(line: &str) -> Result<Vec<Float>, String> {
+    line.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|value| !value.is_empty())
+        .map(|value| value.parse::<Float>().map_err(|_| format!("Invalid number {}", value)))
+        .collect()
+}
+
+fn eval(arguments: &Arguments) -> Result<(), CliError> {
+    let mut network = load_network(arguments.get_target("network")?)?;
+    let input: Box<dyn BufRead> = match arguments.get("csv") {
+        Some(path) => Box::new(BufReader::new(File::open(path)?)),
+        None => Box::new(BufReader::new(io::stdin()))
     };
 
-    let mut trainer = Trainer::new(parameters, 3, 1, |net| {
-        let scores = (0..runs).fold(0.0, |acc, i| {
-            let input = (i % 2, (i / 2) % 2);
-            let target_result = input.0 ^ input.1;
-            match net.reset_and_evaluate(&vec![input.0 as f64, input.1 as f64, 1.0]) {
-                Ok(result) => {
-                        let tmp = (result[0] - target_result as f64).abs();
-                        acc - tmp.powi(3)
-                },
-                Err(_) => {
-                    println!("error");
-                    acc
-                }
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    for (line_id, line) in input.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        let inputs = parse_inputs(&line).map_err(|error| CliError::Failure(format!("Line {}: {}", line_id + 1, error)))?;
+        if arguments.has("reset") {
+            network.reset();
+        }
+        let outputs = network.evaluate(&inputs).map_err(|error| {
+            CliError::Failure(format!("Line {}: {:?} (the network has {} inputs)", line_id + 1, error, network.inputs))
+        })?;
+        writeln!(stdout, "{}", outputs.iter().map(|output| output.to_string()).collect::<Vec<_>>().join(","))?;
+    }
+    Ok(())
+}
+
+/// Structural statistics of a network
+fn get_statistics(network: &Network) -> Json {
+    let (enabled_genes, nodes) = network.get_size();
+    let mut statistics = BTreeMap::new();
+    statistics.insert("inputs".to_string(), network.inputs.to_json());
+    statistics.insert("outputs".to_string(), network.outputs.len().to_json());
+    statistics.insert("nodes".to_string(), nodes.to_json());
+    statistics.insert("hidden_nodes".to_string(), (nodes - network.inputs - network.outputs.len()).to_json());
+    statistics.insert("memory_cells".to_string(), network.nodes.iter().filter(|node| node.is_memory()).count().to_json());
+    statistics.insert("genes".to_string(), network.genome.len().to_json());
+    statistics.insert("enabled_genes".to_string(), enabled_genes.to_json());
+    statistics.insert("gated_genes".to_string(), network.genome.iter().filter(|gene| !gene.disabled && gene.gate.is_some()).count().to_json());
+    statistics.insert("recurrent".to_string(), network.is_recurrent().to_json());
+
+    let mut activations = BTreeMap::new();
+    for activation in Activation::all().iter() {
+        let count = network.nodes.iter().skip(network.inputs).filter(|node| node.activation == *activation).count();
+        activations.insert(activation.name().to_string(), count.to_json());
+    }
+    statistics.insert("activations".to_string(), Json::Object(activations));
+    let mut aggregations = BTreeMap::new();
+    for aggregation in Aggregation::all().iter() {
+        let count = network.nodes.iter().skip(network.inputs).filter(|node| node.aggregation == *aggregation).count();
+        aggregations.insert(aggregation.name().to_string(), count.to_json());
+    }
+    statistics.insert("aggregations".to_string(), Json::Object(aggregations));
+    Json::Object(statistics)
+}
+
+/// Prints the structural statistics of a network as pretty JSON
+fn inspect(arguments: &Arguments) -> Result<(), CliError> {
+    let network = load_network(arguments.get_target("network")?)?;
+    println!("{}", json::as_pretty_json(&get_statistics(&network)));
+    Ok(())
+}
+
+fn render(arguments: &Arguments) -> Result<(), CliError> {
+    let network = load_network(arguments.get_target("network")?)?;
+    let dot = network.to_dot();
+    let rendered = match arguments.get("format").unwrap_or("dot") {
+        "dot" => dot.into_bytes(),
+        "svg" => {
+            let mut graphviz = Command::new("dot").arg("-Tsvg").stdin(Stdio::piped()).stdout(Stdio::piped()).spawn()
+                .map_err(|error| CliError::Failure(format!("Could not run graphviz (dot): {}", error)))?;
+            graphviz.stdin.take().expect("Missing stdin of graphviz").write_all(dot.as_bytes())?;
+            let result = graphviz.wait_with_output()?;
+            if !result.status.success() {
+                return Err(CliError::Failure(format!("Graphviz failed with {}", result.status)));
             }
+            result.stdout
+        },
+        format => return Err(CliError::Usage(format!("Unknown format {} (available: dot, svg)", format)))
+    };
+    match arguments.get("output") {
+        Some(path) => File::create(path)?.write_all(&rendered)?,
+        None => io::stdout().write_all(&rendered)?
+    }
+    Ok(())
+}
 
+fn run(arguments: &[String]) -> Result<(), CliError> {
+    let (command, arguments) = arguments.split_first().ok_or_else(|| CliError::Usage("Missing subcommand".to_string()))?;
+    match command.as_str() {
+        "train" => train(&Arguments::parse(arguments, &["config", "output", "resume"], &[])?),
+        "eval" => eval(&Arguments::parse(arguments, &["csv"], &["reset"])?),
+        "inspect" => inspect(&Arguments::parse(arguments, &[], &[])?),
+        "render" => render(&Arguments::parse(arguments, &["format", "output"], &[])?),
+        "help" | "--help" => {
+            println!("{}", USAGE);
+            Ok(())
+        },
+        command => Err(CliError::Usage(format!("Unknown subcommand {}", command)))
+    }
+}
 
-            // let max_pos: f64 = 10.0 + thread_rng().gen::<f64>() * 60.0;
-            // let a: f64 = thread_rng().gen::<f64>() * 5.0 + 5.0;
-            // let mut t: f64 = 0.0;
-            // let mut closed = true;
-            // net.network.reset();
-            // while closed && max_pos - 0.5 * a * t * t > 0.0 {
-            //     match net.evaluate(&vec![max_pos / 100.0, a / 100.0, t / 10.0]) {
-            //         Ok(result) => {
-            //                 if result[0] > 0.5 {
-            //                     closed = false;
-            //                 }
-            //         },
-            //         Err(_) => {
-            //             println!("error");
-            //         }
-            //     }
-            //     t += 0.1;
-            // }
-            // let pos = if max_pos - 0.5 * a * t * t > 0.0 {(max_pos - 0.5 * a * t * t) / max_pos} else {999999999999.0};
-            // acc - pos
-        });
-        scores / runs as f64
-    });
-
-    for i in 0..50000 {
-        // println!("{:?}", trainer.next());
-        trainer.next();
-        let mut net = trainer.get_best_network();
-        println!("Generation {}: {:?} {}", i, net.score, trainer.species.len());
-        println!("{:?}, {:?}", net.network.genome.len(), net.network.nodes.len());
-
-        if i % 100 == 0 {
-        // if i == 49999 {
-            println!("{:?}", i);
-            let scores = (0..runs).fold(0.0, |acc, i| {
-                let input = ((i % 2), (i / 2) % 2);
-                let target_result = input.0 ^ input.1;
-                match net.reset_and_evaluate(&vec![input.0 as f64, input.1 as f64, 1.0]) {
-                    Ok(result) => {
-                            println!("{:?} -> {} (guess: {} / {})", input, target_result, result[0].round(), result[0]);
-                            println!("dist: {:?}", (result[0] - target_result as f64).abs());
-                            let tmp = (result[0] - target_result as f64).abs();
-                            acc - tmp.powi(2)
-                    },
-                    Err(_) => {
-                        println!("error");
-                        acc
-                    }
-                }
-            });
-            println!("Score: {:?} for {} runs", scores, runs);
-            let size = net.network.get_size();
-            println!("Size: {} genes and {} nodes", size.0, size.1);
-            println!(" WOHOOO IT LEARNED XOR!!!! (in generation {})", i);
-
-            // for i in 0..10 {
-            //     let max_pos: f64 = 10.0 + thread_rng().gen::<f64>() * 60.0;
-            //     let a: f64 = thread_rng().gen::<f64>() * 5.0 + 5.0;//9.18;
-            //     let mut t: f64 = 0.0;
-            //     let mut closed = true;
-            //     net.network.reset();
-            //     while closed && max_pos - 0.5 * a * t * t > 0.0 {
-            //         t += 0.1;
-            //         match net.evaluate(&vec![max_pos / 100.0, a / 100.0, t / 10.0]) {
-            //             Ok(result) => {
-            //                     if result[0] > 0.5 {
-            //                         closed = false;
-            //                     }
-            //             },
-            //             Err(_) => {
-            //                 println!("error");
-            //             }
-            //         }
-            //     }
-            //     if max_pos - 0.5 * a * t * t > 0.0 {
-            //         println!("{:?}", (max_pos - 0.5 * a * t * t) / max_pos);
-            //     }
-            //     else {
-            //         println!("died: {}", (max_pos - 0.5 * a * t * t) / max_pos);
-            //     };
-            // }
-            // println!("-------");
+fn main() {
+    let arguments = env::args().skip(1).collect::<Vec<_>>();
+    if let Err(error) = run(&arguments) {
+        match error {
+            CliError::Usage(ref message) => eprintln!("{}\n\n{}", message, USAGE),
+            CliError::Config(ref error) => eprintln!("Invalid config: {:?}", error),
+            CliError::TargetMissed => eprintln!("The target score was not reached"),
+            CliError::Failure(ref message) => eprintln!("{}", message)
         }
+        process::exit(error.exit_code());
     }
 }
+
+#[test]
+fn argument_parsing() {
+    let arguments = ["xor", "--config", "a.json", "--reset"].iter().map(|argument| argument.to_string()).collect::<Vec<_>>();
+    let parsed = Arguments::parse(&arguments, &["config"], &["reset"]).unwrap();
+    assert_eq!(parsed.get_target("task").unwrap(), "xor");
+    assert_eq!(parsed.get("config"), Some("a.json"));
+    assert!(parsed.has("reset"));
+
+    assert_eq!(Arguments::parse(&arguments, &["config"], &[]).unwrap_err().exit_code(), 2);
+    assert_eq!(Arguments::parse(&arguments[..2], &["config"], &["reset"]).unwrap_err().exit_code(), 2);
+    assert_eq!(run(&["train".to_string(), "juggling".to_string()]).unwrap_err().exit_code(), 2);
+    assert_eq!(parse_inputs("1, 2.5 -3"), Ok(vec![1.0, 2.5, -3.0]));
+    assert!(parse_inputs("1,x").is_err());
+}
+
+#[test]
+fn network_statistics() {
+    let network = Network::from_text("in a b; out y; h = tanh; a -> h 1.0; h -> y 0.5; y -> h 0.1; b -> y -1.0").unwrap();
+    let statistics = get_statistics(&network);
+    assert_eq!(statistics["hidden_nodes"], Json::U64(1));
+    assert_eq!(statistics["enabled_genes"], Json::U64(4));
+    assert_eq!(statistics["recurrent"], Json::Boolean(true));
+}
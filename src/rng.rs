@@ -0,0 +1,49 @@
+use rand::{Rng, SeedableRng, XorShiftRng};
+
+/// Random number generator of the trainer that is derived from a seed and a stream number
+///
+/// The trainer starts a new stream for every generation, so a run can be reproduced (or continued) from the seed and
+/// the generation counter alone.
+#[derive(Debug, Clone)]
+pub struct TrainerRng {
+    rng: XorShiftRng
+}
+
+impl TrainerRng {
+    pub fn new(seed: u64, stream: u64) -> TrainerRng {
+        let mut state = seed ^ stream.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        let (first, second) = (split_mix(&mut state), split_mix(&mut state));
+        let mut words = [first as u32, (first >> 32) as u32, second as u32, (second >> 32) as u32];
+        // The xorshift generator doesn't accept an all zero state
+        if words == [0; 4] {
+            words[0] = 1;
+        }
+        TrainerRng { rng: XorShiftRng::from_seed(words) }
+    }
+}
+
+impl Rng for TrainerRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+}
+
+/// Step of the SplitMix64 generator, which turns similar seeds into unrelated states
+fn split_mix(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+#[test]
+fn reproducible_streams() {
+    let draw = |seed, stream| {
+        let mut rng = TrainerRng::new(seed, stream);
+        (0..4).map(|_| rng.gen::<u32>()).collect::<Vec<_>>()
+    };
+    assert_eq!(draw(42, 3), draw(42, 3));
+    assert!(draw(42, 3) != draw(42, 4));
+    assert!(draw(42, 3) != draw(43, 3));
+}
@@ -0,0 +1,160 @@
+// Strategies that pick the parents of the offspring of a species
+//
+// The built-in strategies are chosen through the SelectionParameters of the trainer. Other strategies can implement
+// SelectionStrategy and be passed to Species::breed() directly.
+
+use rand::Rng;
+
+use trainer::Score;
+use training_network::ScoredTrainingNetwork;
+
+/// Picks a parent among the networks of a species
+pub trait SelectionStrategy {
+    /// Index of the selected network (the networks are never empty)
+    fn select(&self, networks: &[ScoredTrainingNetwork], rng: &mut dyn Rng) -> usize;
+}
+
+/// Best of the given amount of randomly drawn networks
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tournament {
+    pub size: usize
+}
+
+/// Networks are picked with a probability proportional to their fitness
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FitnessProportional;
+
+/// Networks are picked with a probability proportional to their rank in the species (1 for the worst one)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RankProportional;
+
+/// Every network is picked with the same probability
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Uniform;
+
+impl SelectionStrategy for Tournament {
+    fn select(&self, networks: &[ScoredTrainingNetwork], rng: &mut dyn Rng) -> usize {
+        (1..self.size).fold(random_index(rng, networks.len()), |best, _| {
+            let contender = random_index(rng, networks.len());
+            if networks[contender].fitness > networks[best].fitness { contender } else { best }
+        })
+    }
+}
+
+impl SelectionStrategy for FitnessProportional {
+    fn select(&self, networks: &[ScoredTrainingNetwork], rng: &mut dyn Rng) -> usize {
+        let weights = networks.iter().map(|net| if net.fitness > 0.0 { net.fitness } else { 0.0 }).collect::<Vec<_>>();
+        spin_wheel(&weights, rng)
+    }
+}
+
+impl SelectionStrategy for RankProportional {
+    fn select(&self, networks: &[ScoredTrainingNetwork], rng: &mut dyn Rng) -> usize {
+        let mut order = (0..networks.len()).collect::<Vec<_>>();
+        order.sort_by(|&a, &b| networks[a].fitness.partial_cmp(&networks[b].fitness).expect("Fitness comparison failed"));
+        let mut weights = vec![0.0; networks.len()];
+        for (rank, &index) in order.iter().enumerate() {
+            weights[index] = (rank + 1) as Score;
+        }
+        spin_wheel(&weights, rng)
+    }
+}
+
+impl SelectionStrategy for Uniform {
+    fn select(&self, networks: &[ScoredTrainingNetwork], rng: &mut dyn Rng) -> usize {
+        random_index(rng, networks.len())
+    }
+}
+
+/// Uniformly distributed index below the given length
+fn random_index(mut rng: &mut dyn Rng, length: usize) -> usize {
+    Rng::gen_range(&mut rng, 0, length)
+}
+
+/// Roulette wheel selection (uniform if the weights sum up to nothing)
+fn spin_wheel(weights: &[Score], mut rng: &mut dyn Rng) -> usize {
+    let total = weights.iter().sum::<Score>();
+    if total.is_nan() || total <= 0.0 || total.is_infinite() {
+        return random_index(rng, weights.len());
+    }
+    let mut target = Rng::gen::<Score>(&mut rng) * total;
+    for (index, weight) in weights.iter().enumerate() {
+        if target < *weight {
+            return index;
+        }
+        target -= *weight;
+    }
+    // Rounding can leave a tiny rest, which belongs to the last network that can be picked at all
+    weights.iter().rposition(|weight| *weight > 0.0).expect("Weights sum up to a positive number")
+}
+
+/// Built-in strategy that can be chosen in the training parameters
+#[derive(Debug, Clone, Copy, PartialEq, RustcDecodable, RustcEncodable)]
+pub enum Selection {
+    Tournament,
+    FitnessProportional,
+    RankProportional,
+    Uniform
+}
+
+/// Which strategies pick the parents of the offspring
+#[derive(Debug, Clone, PartialEq, RustcDecodable, RustcEncodable)]
+pub struct SelectionParameters {
+    /// Strategy for both parents of children that are created by crossover
+    pub crossover: Selection,
+    /// Strategy for the parent of children that are mutated copies
+    pub mutation: Selection,
+    /// Amount of networks that compete in a tournament
+    pub tournament_size: usize
+}
+
+impl Default for SelectionParameters {
+    fn default() -> SelectionParameters {
+        SelectionParameters {
+            crossover: Selection::Uniform,
+            mutation: Selection::Uniform,
+            tournament_size: 3
+        }
+    }
+}
+
+impl SelectionParameters {
+    /// Implementation of the given built-in strategy
+    pub fn get_strategy(&self, selection: Selection) -> Box<dyn SelectionStrategy> {
+        match selection {
+            Selection::Tournament => Box::new(Tournament { size: self.tournament_size }),
+            Selection::FitnessProportional => Box::new(FitnessProportional),
+            Selection::RankProportional => Box::new(RankProportional),
+            Selection::Uniform => Box::new(Uniform)
+        }
+    }
+}
+
+#[test]
+fn selection_strategies() {
+    use neatwork::Network;
+    use rng::TrainerRng;
+    use training_network::UnscoredTrainingNetwork;
+
+    let networks = [0.0, 3.0, 1.0].iter().map(|&fitness| {
+        UnscoredTrainingNetwork::new(Network::new_empty(1, 1)).calculate_score(&|_: &mut UnscoredTrainingNetwork| fitness)
+    }).collect::<Vec<_>>();
+    let mut rng = TrainerRng::new(1, 0);
+    let mut count = |strategy: &dyn SelectionStrategy| {
+        let mut counts = [0; 3];
+        for _ in 0..4000 {
+            counts[strategy.select(&networks, &mut rng)] += 1;
+        }
+        counts
+    };
+
+    assert_eq!(count(&FitnessProportional)[0], 0);
+    let counts = count(&RankProportional);
+    assert!(counts[1] > counts[2] && counts[2] > counts[0]);
+    let counts = count(&Uniform);
+    assert!(counts.iter().all(|&count| count > 1000));
+    // The worst network can only win a tournament against itself
+    let counts = count(&Tournament { size: 2 });
+    assert!(counts[0] < 600 && counts[1] > 2000);
+    assert_eq!(count(&Tournament { size: 100 }), [0, 4000, 0]);
+}
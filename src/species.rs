@@ -1,11 +1,14 @@
-use rand::{thread_rng, Rng};
+use std::cmp::Ordering;
+
+use rand::Rng;
 
 use training_network::{ScoredTrainingNetwork, UnscoredTrainingNetwork};
 use trainer::{TrainingParameters, Probability};
 use trainer::Score;
+use selection::SelectionStrategy;
 
 /// A niche that contains multiple networks to protect changes that are less performant at first
-#[derive(Debug)]
+#[derive(Debug, Clone, RustcDecodable, RustcEncodable)]
 pub struct Species {
     pub networks: Vec<ScoredTrainingNetwork>,
     pub score: Score,
@@ -24,44 +27,65 @@ impl Species {
     }
 
     pub fn from(networks: Vec<ScoredTrainingNetwork>) -> Species {
+        let top_score = networks.iter().fold(None, |top: Option<Score>, net| Some(top.map_or(net.score, |top| top.max(net.score))));
         Species {
             networks: networks,
             score: 0.0,
-            top_score: 0.0,
+            top_score: top_score.unwrap_or(0.0),
             staleness: 0
         }
     }
 
+    /// Updates and returns the average score of the networks
     pub fn calculate_score(&mut self) -> Score {
         let score = self.networks.iter().fold(0.0, |acc, net| { acc + net.score }) / self.networks.len() as Score;
         self.score = score;
         score
     }
 
-    pub fn breed(&self, parameters: &TrainingParameters) -> UnscoredTrainingNetwork {
-        let mut net = if thread_rng().gen::<Probability>() < parameters.crossover_probability {
-            let parent1 = &self.networks[thread_rng().gen_range(0, self.networks.len())];
-            let parent2 = &self.networks[thread_rng().gen_range(0, self.networks.len())];
-            parent1.crossover(&parent2, parent1.score > parent2.score)
+    /// Sum of the adjusted fitness of the networks as in NEAT (fitness divided by the size of the species)
+    pub fn adjusted_fitness(&self) -> Score {
+        let size = self.networks.len() as Score;
+        self.networks.iter().fold(0.0, |acc, net| if net.fitness > 0.0 { acc + net.fitness / size } else { acc })
+    }
+
+    /// Creates a mutated child by crossover of two networks or by cloning a single one
+    ///
+    /// The parents are picked by the crossover or the mutation strategy, depending on how the child is created. The
+    /// second parent of a crossover comes from the mate species if there is one. If it can't be crossed with the first
+    /// one (because their inputs or outputs differ), it is picked from this species instead. Parents of the same species
+    /// that can't be crossed give a copy of the first parent.
+    pub fn breed<R: Rng>(&self, parameters: &TrainingParameters, crossover: &dyn SelectionStrategy, mutation: &dyn SelectionStrategy,
+                         mate: Option<&Species>, rng: &mut R) -> UnscoredTrainingNetwork {
+        let mut net = if rng.gen::<Probability>() < parameters.crossover_probability {
+            let parent1 = &self.networks[crossover.select(&self.networks, rng)];
+            let parent2 = match mate.map(|mate| &mate.networks[crossover.select(&mate.networks, rng)]) {
+                Some(parent2) if parent1.can_cross_with(parent2) => parent2,
+                _ => &self.networks[crossover.select(&self.networks, rng)]
+            };
+            parent1.crossover(parent2, parent1.score > parent2.score, &parameters.gene_mutation, rng)
+                .unwrap_or_else(|_| UnscoredTrainingNetwork::new(parent1.network.clone()))
         } else {
-            UnscoredTrainingNetwork::new(self.networks[thread_rng().gen_range(0, self.networks.len())].network.clone())
+            UnscoredTrainingNetwork::new(self.networks[mutation.select(&self.networks, rng)].network.clone())
         };
 
-        net.mutate(parameters);
+        net.mutate(parameters, rng);
 
         net
     }
 
+    /// Sorts by score, scores that aren't numbers count as the lowest ones (like in FitnessTransform)
     pub fn sort(&mut self, best_first: bool) {
         self.networks.sort_by(|a, b| // Sort so that the net w/ the highest score is at index 0
             if best_first {
-                b.score.partial_cmp(&a.score).expect("F64 score comparison failed")
+                compare_scores(b.score, a.score)
             } else {
-                a.score.partial_cmp(&b.score).expect("F64 score comparison failed")
+                compare_scores(a.score, b.score)
             }
         );
     }
 
+    /// Keeps the given percentage of the best networks (but at least one)
     pub fn cull(&mut self, percentage: Probability) {
         self.sort(true);
 
@@ -71,3 +95,53 @@ impl Species {
         self.networks.truncate(resulting_size);
     }
 }
+
+fn compare_scores(a: Score, b: Score) -> Ordering {
+    let number = |score: Score| if score.is_nan() { Score::NEG_INFINITY } else { score };
+    number(a).partial_cmp(&number(b)).unwrap_or(Ordering::Equal)
+}
+
+#[test]
+fn breed_with_mate() {
+    use rand::thread_rng;
+    use neatwork::{Network, Gene};
+    use selection::Selection;
+
+    let scored = |network: Network, score: Score| ScoredTrainingNetwork { network: network, score: score, fitness: score };
+    let own = Species::from((0..3).map(|_| scored(Network::new_empty(1, 1), 0.5)).collect());
+    // Only the (fitter) networks of the mate have the recurrent gene, so the child can only get it from there
+    let mate = Species::from((0..3).map(|_| {
+        let mut network = Network::new_empty(1, 1);
+        network.genome.push(Gene::random(1, 1, false));
+        scored(network, 1.0)
+    }).collect());
+    let parameters = TrainingParameters {
+        crossover_probability: 1.0,
+        add_gene_probability: 0.0,
+        add_node_probability: 0.0,
+        memory_cell_probability: 0.0,
+        mutate_aggregation_probability: 0.0,
+        mutate_gene_probability: 0.0,
+        gene_enable_probability: 0.0,
+        gene_disable_probability: 0.0,
+        ..TrainingParameters::default()
+    };
+    let strategy = parameters.selection.get_strategy(Selection::Uniform);
+    let mut rng = thread_rng();
+    for _ in 0..10 {
+        let child = own.breed(&parameters, &*strategy, &*strategy, Some(&mate), &mut rng);
+        assert!(child.network.genome.iter().any(|gene| gene.link == (1, 1)));
+        let child = own.breed(&parameters, &*strategy, &*strategy, None, &mut rng);
+        assert!(child.network.genome.iter().all(|gene| gene.link != (1, 1)));
+    }
+
+    // A mate whose networks can't be crossed (two inputs instead of one) is skipped instead of turning the child into a copy
+    let mut network = Network::new_empty(2, 1);
+    network.genome.push(Gene::random(2, 2, false));
+    let incompatible = Species::from(vec![scored(network, 1.0)]);
+    for _ in 0..10 {
+        let child = own.breed(&parameters, &*strategy, &*strategy, Some(&incompatible), &mut rng);
+        assert_eq!(child.network.inputs, 1);
+        assert!(child.network.genome.iter().all(|gene| gene.link != (2, 2)));
+    }
+}
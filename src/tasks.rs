@@ -0,0 +1,95 @@
+// Built-in problems that can be trained without writing a fitness function
+//
+// Every task appends a constant 1.0 to its inputs, so the networks have a bias to work with.
+
+use std::f64::consts::PI;
+
+use neatwork::Float;
+use trainer::Score;
+use training_network::UnscoredTrainingNetwork;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Task {
+    Xor,
+    And,
+    /// Maps x in [0, 1] to (sin(2 pi x) + 1) / 2
+    Sine
+}
+
+impl Task {
+    /// List of all available tasks
+    pub fn all() -> [Task; 3] {
+        [Task::Xor, Task::And, Task::Sine]
+    }
+
+    /// Lowercase name of the task
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Task::Xor => "xor",
+            Task::And => "and",
+            Task::Sine => "sine"
+        }
+    }
+
+    /// Looks up a task by its name()
+    pub fn from_name(name: &str) -> Option<Task> {
+        Task::all().iter().find(|task| task.name() == name).cloned()
+    }
+
+    /// Amount of network inputs (including the bias)
+    pub fn inputs(&self) -> usize {
+        match *self {
+            Task::Xor | Task::And => 3,
+            Task::Sine => 2
+        }
+    }
+
+    pub fn outputs(&self) -> usize {
+        1
+    }
+
+    /// Inputs and expected outputs that the network is scored on
+    pub fn cases(&self) -> Vec<(Vec<Float>, Vec<Float>)> {
+        match *self {
+            Task::Xor | Task::And => (0..4).map(|i| {
+                let (a, b) = (i % 2, i / 2);
+                let target = if *self == Task::Xor { a ^ b } else { a & b };
+                (vec![a as Float, b as Float, 1.0], vec![target as Float])
+            }).collect(),
+            Task::Sine => (0..17).map(|i| {
+                let x = i as f64 / 16.0;
+                (vec![x as Float, 1.0], vec![((2.0 * PI * x).sin() as Float + 1.0) / 2.0])
+            }).collect()
+        }
+    }
+
+    /// Negative mean cubed error over all cases, so a perfect network scores 0
+    pub fn fitness(&self, net: &mut UnscoredTrainingNetwork) -> Score {
+        let cases = self.cases();
+        let error = cases.iter().fold(0.0, |acc, &(ref inputs, ref targets)| {
+            match net.reset_and_evaluate(inputs) {
+                Ok(outputs) => outputs.iter().zip(targets.iter()).fold(acc, |acc, (output, target)| {
+                    acc + (*output as Score - *target as Score).abs().powi(3)
+                }),
+                Err(_) => acc + 1.0
+            }
+        });
+        -error / cases.len() as Score
+    }
+}
+
+#[test]
+fn task_fitness() {
+    use neatwork::{NetworkBuilder, Activation};
+
+    // a AND b = relu(a + b - 1)
+    let network = NetworkBuilder::new()
+        .input("a").input("b").input("bias").output("y").activation("y", Activation::Relu)
+        .connect("a", "y", 1.0).connect("b", "y", 1.0).connect("bias", "y", -1.0)
+        .build().unwrap();
+    let mut net = UnscoredTrainingNetwork::new(network);
+    assert_eq!(Task::And.fitness(&mut net), 0.0);
+    assert_eq!(Task::Xor.fitness(&mut net), -0.75);
+    assert_eq!(Task::from_name("sine"), Some(Task::Sine));
+    assert!(Task::all().iter().all(|task| task.cases().iter().all(|case| case.0.len() == task.inputs())));
+}
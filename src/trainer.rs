@@ -1,50 +1,232 @@
+use std::panic;
+use std::path::Path;
+use std::sync::Mutex;
+use std::thread;
+
+use num_cpus;
 use rand::{thread_rng, Rng};
 
+use checkpoint::{Checkpoint, CheckpointError};
+use fitness::FitnessTransform;
+use rng::TrainerRng;
+use selection::{Selection, SelectionParameters};
 use species::Species;
 use training_network::{UnscoredTrainingNetwork, ScoredTrainingNetwork};
-use neatwork::Network;
+use neatwork::{Float, Network};
 
+/// Fitness of a network (higher is better)
 pub type Score = f64;
 pub type Probability = f32;
 
+/// Hyperparameters of the evolution
+#[derive(Debug, Clone, PartialEq, RustcDecodable, RustcEncodable)]
 pub struct TrainingParameters {
     pub population_size: usize,
     pub cull_percentage: Probability,
     pub crossover_probability: Probability,
     pub add_gene_probability: Probability,
     pub add_node_probability: Probability,
+    pub memory_cell_probability: Probability,
+    pub mutate_aggregation_probability: Probability,
     pub mutate_gene_probability: Probability,
     pub gene_enable_probability: Probability,
     pub gene_disable_probability: Probability,
-    pub staleness_maximum: usize
+    pub staleness_maximum: usize,
+    /// How scores are turned into the fitness that decides about the offspring
+    pub fitness_transform: FitnessTransform,
+    /// Seed of all random decisions of the trainer (a random one is picked if there is none)
+    pub seed: Option<u64>,
+    /// Amount of threads that score networks in parallel (0 for one per CPU)
+    pub threads: usize,
+    pub speciation: SpeciationParameters,
+    pub gene_mutation: GeneMutationParameters,
+    pub selection: SelectionParameters,
+    pub elitism: ElitismParameters,
+    pub interspecies_mating: InterspeciesParameters
+}
+
+/// Coefficients of the compatibility distance that decides whether two networks belong to the same species
+#[derive(Debug, Clone, PartialEq, RustcDecodable, RustcEncodable)]
+pub struct SpeciationParameters {
+    /// Weight of the share of genes that only exist in one of the networks (C1)
+    pub disjoint_coefficient: f64,
+    /// Weight of the average weight difference of the shared genes (C2)
+    pub weight_coefficient: f64,
+    /// Networks with a smaller distance than this are compatible (DELTA_MAX)
+    pub threshold: f64
+}
+
+/// How the weight of a gene changes upon mutation and crossover
+#[derive(Debug, Clone, PartialEq, RustcDecodable, RustcEncodable)]
+pub struct GeneMutationParameters {
+    /// Probability of replacing the weight by a random one instead of shifting it
+    pub reset_probability: Probability,
+    /// Maximum amount by which the weight is shifted
+    pub strength: Float,
+    /// Probability that a child keeps the weight of the fitter parent for a gene both parents share
+    pub fitter_weight_probability: Probability
+}
+
+/// Which networks are copied into the next generation unchanged
+///
+/// The network with the highest score of the population is always kept, even if its species is too small.
+#[derive(Debug, Clone, PartialEq, RustcDecodable, RustcEncodable)]
+pub struct ElitismParameters {
+    /// Amount of the best networks of a species that are kept (as long as the species has room for that many offspring)
+    pub count: usize,
+    /// Species with fewer networks than this don't keep any
+    pub minimum_species_size: usize
+}
+
+/// Crossover between networks of different species
+#[derive(Debug, Clone, PartialEq, RustcDecodable, RustcEncodable)]
+pub struct InterspeciesParameters {
+    /// Probability that the second parent of a crossover comes from another species
+    pub probability: Probability,
+    /// Strategy that picks the other species, applied to the best networks of the species
+    pub species: Selection
+}
+
+impl Default for TrainingParameters {
+    fn default() -> TrainingParameters {
+        TrainingParameters {
+            population_size: 15,
+            cull_percentage: 0.5,
+            crossover_probability: 0.75,
+            add_gene_probability: 0.03,
+            add_node_probability: 0.05,
+            memory_cell_probability: 0.01,
+            mutate_aggregation_probability: 0.01,
+            mutate_gene_probability: 0.9,
+            gene_enable_probability: 0.4,
+            gene_disable_probability: 0.2,
+            staleness_maximum: 15,
+            fitness_transform: FitnessTransform::default(),
+            seed: None,
+            threads: 0,
+            speciation: SpeciationParameters::default(),
+            gene_mutation: GeneMutationParameters::default(),
+            selection: SelectionParameters::default(),
+            elitism: ElitismParameters::default(),
+            interspecies_mating: InterspeciesParameters::default()
+        }
+    }
+}
+
+impl Default for InterspeciesParameters {
+    fn default() -> InterspeciesParameters {
+        InterspeciesParameters {
+            probability: 0.001,
+            species: Selection::Uniform
+        }
+    }
+}
+
+impl Default for ElitismParameters {
+    fn default() -> ElitismParameters {
+        ElitismParameters {
+            count: 1,
+            minimum_species_size: 1
+        }
+    }
+}
+
+impl Default for SpeciationParameters {
+    fn default() -> SpeciationParameters {
+        SpeciationParameters {
+            disjoint_coefficient: 1.0,
+            weight_coefficient: 0.4,
+            threshold: 10.0
+        }
+    }
+}
+
+impl Default for GeneMutationParameters {
+    fn default() -> GeneMutationParameters {
+        GeneMutationParameters {
+            reset_probability: 0.1,
+            strength: 0.1,
+            fitter_weight_probability: 0.5
+        }
+    }
 }
 
-pub struct Trainer<F> where F: Fn(&mut UnscoredTrainingNetwork) -> Score {
+/// Population of networks that evolves by scoring every new network with the given closure
+///
+/// The closure may be called from several threads at once. As long as it is deterministic, a trainer with a seed
+/// produces the same populations regardless of the amount of threads.
+pub struct Trainer<F> where F: Fn(&mut UnscoredTrainingNetwork) -> Score + Send + Sync {
     parameters: TrainingParameters,
     pub species: Vec<Species>,
+    generation: usize,
+    seed: u64,
     eval_closure: F
 }
 
-impl<F> Trainer<F> where F : Fn(&mut UnscoredTrainingNetwork) -> Score {
+impl<F> Trainer<F> where F : Fn(&mut UnscoredTrainingNetwork) -> Score + Send + Sync {
+    /// Creates and scores an initial population of fully connected networks without hidden nodes
     pub fn new(parameters: TrainingParameters, inputs: usize, outputs: usize, closure: F) -> Trainer<F> {
+        let seed = parameters.seed.unwrap_or_else(|| thread_rng().gen());
+        let mut rng = TrainerRng::new(seed, 0);
+        let networks = (0..parameters.population_size).map(|_| {
+            UnscoredTrainingNetwork::new(Network::new_empty_with_rng(inputs, outputs, &mut rng))
+        }).collect();
         Trainer {
-            species: vec![Species::from(
-                (0..parameters.population_size).map(|_| {
-                    let net = UnscoredTrainingNetwork::new(Network::new_empty(inputs, outputs));
-                    net.calculate_score(&closure)
-                }).collect()
-            )],
+            species: vec![Species::from(score_networks(networks, &closure, parameters.threads))],
             parameters: parameters,
+            generation: 0,
+            seed: seed,
             eval_closure: closure
         }
     }
 
+    /// Continues the run that was saved with checkpoint(), scoring new networks with the given closure
+    ///
+    /// The populations that follow are the same as those of the original run, as long as the closure scores networks
+    /// the same way.
+    pub fn resume<P: AsRef<Path>>(path: P, closure: F) -> Result<Trainer<F>, CheckpointError> {
+        let checkpoint = Checkpoint::load(path)?;
+        Ok(Trainer {
+            parameters: checkpoint.parameters,
+            species: checkpoint.species,
+            generation: checkpoint.generation,
+            seed: checkpoint.seed,
+            eval_closure: closure
+        })
+    }
+
+    /// Saves everything but the closure, so the run can be continued with resume()
+    pub fn checkpoint<P: AsRef<Path>>(&self, path: P) -> Result<(), CheckpointError> {
+        Checkpoint::new(self.parameters.clone(), self.seed, self.generation, self.species.clone()).save(path)
+    }
+
+    /// Seed of the run (which is the one of the parameters if they have one)
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn get_parameters(&self) -> &TrainingParameters {
+        &self.parameters
+    }
+
+    /// Amount of generations that were bred since the trainer has been created
+    pub fn get_generation(&self) -> usize {
+        self.generation
+    }
+
+    /// Copy of the network with the highest score in the population
     pub fn get_best_network(&mut self) -> ScoredTrainingNetwork {
+        let (species_id, network_id) = self.find_best_network();
+        self.species[species_id].networks[network_id].clone()
+    }
+
+    /// Species and network index of the network with the highest score (the first one if several are equal)
+    fn find_best_network(&self) -> (usize, usize) {
         let mut current_species_id = 0;
         let mut current_network_id = 0;
-        let mut current_score = 0.0;
-        for (species_id, species) in self.species.iter_mut().enumerate() {
-            for (network_id, network) in species.networks.iter_mut().enumerate() {
+        let mut current_score = Score::NEG_INFINITY;
+        for (species_id, species) in self.species.iter().enumerate() {
+            for (network_id, network) in species.networks.iter().enumerate() {
                 if network.score > current_score {
                     current_species_id = species_id;
                     current_network_id = network_id;
@@ -52,55 +234,51 @@ impl<F> Trainer<F> where F : Fn(&mut UnscoredTrainingNetwork) -> Score {
                 }
             }
         }
-        self.species[current_species_id].networks[current_network_id].clone()
+        (current_species_id, current_network_id)
     }
 
-    fn delete_weak_species(&mut self) {
-        let total_average_score = self.get_total_avg_score();
-        let mut dead_species_ids = Vec::new();
-        for (species_id, species) in self.species.iter_mut().enumerate() {
-            species.calculate_score(); // This is overkill. A re-estimation from all the net scores would be sufficient instead of a full re-evaluation of every network
-            let breed = species.score / total_average_score * self.parameters.population_size as f64;
-            if breed < 1.0 {
-                dead_species_ids.push(species_id);
+    /// Amount of networks that every species contributes to the next generation (summing up to the population size)
+    ///
+    /// Species get offspring in proportion to their adjusted fitness, which is based on the transformed scores of the
+    /// whole population. If no network has any fitness, every species gets the same share.
+    fn allocate_offspring(&mut self) -> Vec<usize> {
+        let scores = self.species.iter().flat_map(|species| species.networks.iter().map(|net| net.score)).collect::<Vec<_>>();
+        let mut fitness = self.parameters.fitness_transform.apply(&scores).into_iter();
+        let shares = self.species.iter_mut().map(|species| {
+            for net in species.networks.iter_mut() {
+                net.fitness = fitness.next().expect("Every network has a fitness");
             }
-        }
-        for dead_species_id in dead_species_ids.into_iter().rev() {
-            self.species.swap_remove(dead_species_id);
-        }
+            species.calculate_score();
+            species.adjusted_fitness()
+        }).collect::<Vec<_>>();
+        allocate(&shares, self.parameters.population_size)
     }
 
+    /// Removes the species whose best network hasn't improved for too long (except the one of the best network)
     fn delete_stale_species(&mut self) {
-        let global_top_score = self.get_best_network().score;
+        let (champion_species_id, _) = self.find_best_network();
         let mut dead_species = Vec::new();
         for (sid, species) in self.species.iter_mut().enumerate() {
-            species.sort(false); // Sort ascending
-            if species.networks.len() > 1 && species.networks[1].score > species.top_score {
-                species.top_score = species.networks[1].score;
+            species.sort(true);
+            if species.networks[0].score > species.top_score {
+                species.top_score = species.networks[0].score;
                 species.staleness = 0;
             } else {
                 species.staleness += 1;
             }
-            if species.staleness > self.parameters.staleness_maximum && species.top_score < global_top_score {
+            if species.staleness > self.parameters.staleness_maximum && sid != champion_species_id {
                 dead_species.push(sid);
             }
         }
-        for species_id in dead_species {
+        // Removing from the back only moves species that are kept
+        for species_id in dead_species.into_iter().rev() {
             self.species.swap_remove(species_id);
         }
     }
 
-    fn get_total_avg_score(&mut self) -> Score {
-        let mut total_average_score = 0.0;
-        for species in self.species.iter_mut() {
-            total_average_score += species.calculate_score();
-        }
-        total_average_score
-    }
-
     fn add_to_population(&mut self, child: ScoredTrainingNetwork) {
         for species in self.species.iter_mut() {
-            if species.networks[0].is_compatible_with(&child) {
+            if species.networks[0].is_compatible_with(&child, &self.parameters.speciation) {
                 species.networks.push(child);
                 return
             }
@@ -108,43 +286,363 @@ impl<F> Trainer<F> where F : Fn(&mut UnscoredTrainingNetwork) -> Score {
         self.species.push(Species::from(vec!(child)));
     }
 
-    fn get_current_population_size(&self) -> usize {
+    /// Amount of networks in all species
+    pub fn get_current_population_size(&self) -> usize {
         self.species.iter().fold(0, |acc, species| { acc + species.networks.len() })
     }
 
-    fn next_generation(&mut self) {
+    /// Removes stale species and replaces the population with scored offspring of the remaining ones
+    ///
+    /// Every species keeps its elites (see ElitismParameters) and breeds the rest of its share of the population from
+    /// the networks that survive culling. Species without a share die out. The population size stays the same, and
+    /// the best network is always kept, so the best score never decreases (as long as the fitness closure is
+    /// deterministic).
+    pub fn next_generation(&mut self) {
         self.delete_stale_species();
-        for species in self.species.iter_mut() {
-            species.cull(self.parameters.cull_percentage);
+        let (champion_species_id, _) = self.find_best_network();
+        let mut offspring = self.allocate_offspring();
+        if offspring[champion_species_id] == 0 {
+            let donor = (0..offspring.len()).max_by_key(|&sid| offspring[sid]).expect("There is at least one species");
+            offspring[donor] -= 1;
+            offspring[champion_species_id] = 1;
         }
-        self.delete_weak_species(); // This recalculates the species scores implicitly
-        let tas = self.get_total_avg_score();
+        let mut rng = TrainerRng::new(self.seed, self.generation as u64 + 1);
         let mut children = Vec::new();
+        // Species with offspring together with the amount of children they breed and their elites
+        let mut breeding = Vec::new();
         {
             let parameters = &self.parameters;
-            for species in self.species.iter_mut() {
-                let breed = (species.score / tas * self.parameters.population_size as f64) as usize - 1;
-                children.append(&mut (1..breed).map(|_| {
-                    species.breed(parameters)
-                }).collect());
-                species.cull(0.0);
+            for (sid, (mut species, count)) in self.species.drain(..).zip(offspring).enumerate() {
+                if count == 0 {
+                    continue;
+                }
+                species.sort(true);
+                let mut elites = if species.networks.len() >= parameters.elitism.minimum_species_size { parameters.elitism.count } else { 0 };
+                if sid == champion_species_id {
+                    elites = elites.max(1);
+                }
+                let elites = elites.min(count).min(species.networks.len());
+                let kept = species.networks[..elites.max(1)].to_vec();
+                species.cull(parameters.cull_percentage);
+                breeding.push((species, count - elites, elites, kept));
+            }
+
+            let crossover = parameters.selection.get_strategy(parameters.selection.crossover);
+            let mutation = parameters.selection.get_strategy(parameters.selection.mutation);
+            let mate_selection = parameters.selection.get_strategy(parameters.interspecies_mating.species);
+            // Best network of every species to pick interspecies mates from. Only networks with the same amount of inputs
+            // and outputs can be crossed, so the champions are grouped by those (keeping the order of the species).
+            let io_size = |sid: usize| {
+                let network = &breeding[sid].0.networks[0].network;
+                (network.inputs, network.outputs.len())
+            };
+            let mut order = (0..breeding.len()).collect::<Vec<_>>();
+            order.sort_by_key(|&sid| io_size(sid));
+            let mut champions = order.iter().map(|&sid| breeding[sid].0.networks[0].clone()).collect::<Vec<_>>();
+            for (sid, &(ref species, count, _, _)) in breeding.iter().enumerate() {
+                let position = order.iter().position(|&other| other == sid).expect("Every species is ordered");
+                let start = order.iter().position(|&other| io_size(other) == io_size(sid)).expect("The species is in its group");
+                let end = order.iter().rposition(|&other| io_size(other) == io_size(sid)).expect("The species is in its group");
+                // The own champion is taken out while the species breeds, so the mates are the rest of its group
+                let own = champions.remove(position);
+                {
+                    let mates = &champions[start..end];
+                    for _ in 0..count {
+                        let mate = if !mates.is_empty() && rng.gen::<Probability>() < parameters.interspecies_mating.probability {
+                            let other = start + mate_selection.select(mates, &mut rng);
+                            Some(&breeding[order[if other < position { other } else { other + 1 }]].0)
+                        } else {
+                            None
+                        };
+                        children.push(species.breed(parameters, &*crossover, &*mutation, mate, &mut rng));
+                    }
+                }
+                champions.insert(position, own);
             }
         }
-        while children.len() + self.get_current_population_size() < self.parameters.population_size {
-            let species = &self.species[thread_rng().gen_range(0, self.species.len())];
-            children.push(species.breed(&self.parameters));
+        // Species without elites keep their best network until their offspring has been sorted into species
+        let mut representatives = Vec::new();
+        for (mut species, _, elites, kept) in breeding {
+            if elites == 0 {
+                representatives.push(self.species.len());
+            }
+            species.networks = kept;
+            self.species.push(species);
         }
-        for child in children.into_iter() {
-            let child = child.calculate_score(&self.eval_closure);
+        for child in score_networks(children, &self.eval_closure, self.parameters.threads) {
             self.add_to_population(child);
         }
+        for sid in representatives.into_iter().rev() {
+            self.species[sid].networks.remove(0);
+            if self.species[sid].networks.is_empty() {
+                self.species.remove(sid);
+            }
+        }
+        self.generation += 1;
     }
 }
 
-impl<F> Iterator for Trainer<F> where F : Fn(&mut UnscoredTrainingNetwork) -> Score {
+/// Every step breeds one generation and yields the amount of species
+impl<F> Iterator for Trainer<F> where F : Fn(&mut UnscoredTrainingNetwork) -> Score + Send + Sync {
     type Item = (usize, usize);
     fn next(&mut self) -> Option<Self::Item> {
         self.next_generation();
         Some((self.species.len(), 0))
     }
 }
+
+/// Splits the total into integer parts proportional to the shares with the largest remainder method
+///
+/// Every part gets the integer part of its quota first, and the rest goes to the largest fractional parts (the first
+/// of equal ones). The total is split evenly if the shares don't sum up to a positive number.
+fn allocate(shares: &[Score], total: usize) -> Vec<usize> {
+    let sum = shares.iter().sum::<Score>();
+    let quotas = shares.iter().map(|share| {
+        if sum > 0.0 && sum.is_finite() { share / sum * total as Score } else { total as Score / shares.len() as Score }
+    }).collect::<Vec<_>>();
+    let mut parts = quotas.iter().map(|quota| quota.floor() as usize).collect::<Vec<_>>();
+    let mut order = (0..shares.len()).collect::<Vec<_>>();
+    order.sort_by(|&a, &b| {
+        let (remainder_a, remainder_b) = (quotas[a] - quotas[a].floor(), quotas[b] - quotas[b].floor());
+        remainder_b.partial_cmp(&remainder_a).expect("Quota comparison failed")
+    });
+    let remaining = total.saturating_sub(parts.iter().sum());
+    for &index in order.iter().cycle().take(remaining) {
+        parts[index] += 1;
+    }
+    parts
+}
+
+/// Scores the networks on the given amount of threads (0 for one per CPU) and returns them in their original order
+fn score_networks<F>(networks: Vec<UnscoredTrainingNetwork>, closure: &F, threads: usize) -> Vec<ScoredTrainingNetwork>
+    where F: Fn(&mut UnscoredTrainingNetwork) -> Score + Send + Sync {
+    let threads = if threads == 0 { num_cpus::get() } else { threads }.min(networks.len());
+    if threads <= 1 {
+        return networks.into_iter().map(|net| net.calculate_score(closure)).collect();
+    }
+
+    // Every worker takes the next network from the queue, so a slow evaluation doesn't hold up the others
+    let queue = Mutex::new(networks.into_iter().enumerate());
+    let mut scored = thread::scope(|scope| {
+        let workers = (0..threads).map(|_| scope.spawn(|| {
+            let mut scored = Vec::new();
+            loop {
+                let next = queue.lock().expect("Network queue poisoned").next();
+                match next {
+                    Some((index, net)) => scored.push((index, net.calculate_score(closure))),
+                    None => return scored
+                }
+            }
+        })).collect::<Vec<_>>();
+        workers.into_iter().flat_map(|worker| worker.join().unwrap_or_else(|error| panic::resume_unwind(error))).collect::<Vec<_>>()
+    });
+    scored.sort_by_key(|&(index, _)| index);
+    scored.into_iter().map(|(_, net)| net).collect()
+}
+
+#[test]
+fn generation_loop() {
+    let parameters = TrainingParameters { population_size: 10, ..TrainingParameters::default() };
+    let mut trainer = Trainer::new(parameters, 2, 1, |net: &mut UnscoredTrainingNetwork| {
+        match net.reset_and_evaluate(&vec![1.0, 0.5]) {
+            Ok(result) => 1.0 / (1.0 + (result[0] - 0.25).abs()),
+            Err(_) => 0.0
+        }
+    });
+    assert_eq!(trainer.get_current_population_size(), 10);
+
+    for _ in 0..3 {
+        trainer.next_generation();
+    }
+    assert_eq!(trainer.get_generation(), 3);
+    assert!(trainer.get_current_population_size() >= 10);
+    let best = trainer.get_best_network();
+    assert!(trainer.species.iter().all(|species| species.networks.iter().all(|net| net.score <= best.score)));
+}
+
+#[test]
+fn offspring_allocation() {
+    assert_eq!(allocate(&[1.0, 1.0, 1.0], 10), vec![4, 3, 3]);
+    assert_eq!(allocate(&[0.5, 0.3, 0.2], 7), vec![4, 2, 1]);
+    assert_eq!(allocate(&[3.0, 0.0], 5), vec![5, 0]);
+    assert_eq!(allocate(&[0.0, 0.0], 5), vec![3, 2]);
+    assert_eq!(allocate(&[1.0, Score::INFINITY], 4), vec![2, 2]);
+    assert_eq!(allocate(&[2.0, 1.0, 1.0, 1.0], 6).iter().sum::<usize>(), 6);
+}
+
+#[test]
+fn negative_scores() {
+    use tasks::Task;
+
+    let parameters = TrainingParameters { population_size: 15, seed: Some(11), ..TrainingParameters::default() };
+    let mut trainer = Trainer::new(parameters, 3, 1, |net: &mut UnscoredTrainingNetwork| Task::Xor.fitness(net));
+    for _ in 0..10 {
+        trainer.next_generation();
+        assert_eq!(trainer.get_current_population_size(), 15);
+    }
+    // The best network is found even though all scores are negative
+    let best = trainer.get_best_network().score;
+    assert!(trainer.species.iter().all(|species| species.networks.iter().all(|net| net.score <= best)));
+
+    // All networks having the same score is no reason to stop breeding
+    let mut trainer = Trainer::new(TrainingParameters::default(), 2, 1, |_: &mut UnscoredTrainingNetwork| 0.0);
+    trainer.next_generation();
+    assert_eq!(trainer.get_current_population_size(), 15);
+
+    // Scores that aren't numbers are the worst ones instead of aborting the training
+    let fitness = |net: &mut UnscoredTrainingNetwork| {
+        match net.reset_and_evaluate(&vec![1.0, 0.0, 1.0]) {
+            Ok(ref outputs) if outputs[0] > 0.5 => Task::Xor.fitness(net),
+            _ => Score::NAN
+        }
+    };
+    let parameters = TrainingParameters { population_size: 15, seed: Some(11), ..TrainingParameters::default() };
+    let mut trainer = Trainer::new(parameters, 3, 1, fitness);
+    let mut not_a_number = 0;
+    for _ in 0..10 {
+        trainer.next_generation();
+        assert_eq!(trainer.get_current_population_size(), 15);
+        not_a_number += trainer.species.iter().flat_map(|species| species.networks.iter()).filter(|net| net.score.is_nan()).count();
+        for species in trainer.species.iter_mut() {
+            species.sort(true);
+            assert!(!species.networks[0].score.is_nan() || species.networks.iter().all(|net| net.score.is_nan()));
+        }
+    }
+    assert!(not_a_number > 0);
+    assert!(!trainer.get_best_network().score.is_nan());
+}
+
+#[test]
+fn elitism() {
+    use tasks::Task;
+
+    for &(count, minimum_species_size) in [(0, 1), (2, 1), (3, 20)].iter() {
+        let parameters = TrainingParameters {
+            population_size: 20,
+            seed: Some(count as u64),
+            speciation: SpeciationParameters { threshold: 0.3, ..SpeciationParameters::default() },
+            elitism: ElitismParameters { count: count, minimum_species_size: minimum_species_size },
+            ..TrainingParameters::default()
+        };
+        let mut trainer = Trainer::new(parameters, 3, 1, |net: &mut UnscoredTrainingNetwork| Task::Xor.fitness(net));
+        let mut best = trainer.get_best_network();
+        for _ in 0..15 {
+            trainer.next_generation();
+            assert_eq!(trainer.get_current_population_size(), 20);
+            // The champion survives unchanged
+            assert!(trainer.species.iter().any(|species| species.networks.iter().any(|net| net.network == best.network)));
+            let next = trainer.get_best_network();
+            assert!(next.score >= best.score);
+            best = next;
+        }
+    }
+}
+
+#[test]
+fn interspecies_mating() {
+    use tasks::Task;
+
+    let parameters = TrainingParameters {
+        population_size: 30,
+        seed: Some(5),
+        speciation: SpeciationParameters { threshold: 0.3, ..SpeciationParameters::default() },
+        interspecies_mating: InterspeciesParameters { probability: 1.0, species: Selection::FitnessProportional },
+        ..TrainingParameters::default()
+    };
+    let mut trainer = Trainer::new(parameters, 3, 1, |net: &mut UnscoredTrainingNetwork| Task::Xor.fitness(net));
+    for _ in 0..10 {
+        trainer.next_generation();
+        assert_eq!(trainer.get_current_population_size(), 30);
+    }
+    assert!(trainer.species.len() > 1);
+}
+
+#[test]
+fn checkpoint_and_resume() {
+    use std::env;
+    use std::fs;
+
+    let fitness = |net: &mut UnscoredTrainingNetwork| {
+        match net.reset_and_evaluate(&vec![1.0, 0.5]) {
+            Ok(result) => 1.0 / (1.0 + (result[0] - 0.25).abs()),
+            Err(_) => 0.0
+        }
+    };
+    let parameters = TrainingParameters { population_size: 12, seed: Some(3), ..TrainingParameters::default() };
+    let mut trainer = Trainer::new(parameters, 2, 1, fitness);
+    for _ in 0..3 {
+        trainer.next_generation();
+    }
+    let path = env::temp_dir().join(format!("neat_trainer_checkpoint_{}.json", ::std::process::id()));
+    trainer.checkpoint(&path).unwrap();
+    let mut resumed = Trainer::resume(&path, fitness).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(resumed.get_generation(), 3);
+    assert_eq!(resumed.get_seed(), 3);
+    assert_eq!(resumed.get_parameters(), trainer.get_parameters());
+    let population = |trainer: &Trainer<_>| {
+        trainer.species.iter().map(|species| {
+            let networks = species.networks.iter().map(|net| (net.network.clone(), net.score)).collect::<Vec<_>>();
+            (networks, species.score, species.top_score, species.staleness)
+        }).collect::<Vec<_>>()
+    };
+    assert_eq!(population(&resumed), population(&trainer));
+
+    // Both runs keep breeding the same networks
+    trainer.next_generation();
+    resumed.next_generation();
+    assert_eq!(population(&resumed), population(&trainer));
+    assert!(Trainer::resume("/nonexistent/checkpoint.json", fitness).is_err());
+}
+
+#[test]
+fn resume_with_infinite_scores() {
+    use std::env;
+    use std::fs;
+
+    // Networks that fail to evaluate get the worst possible score
+    let fitness = |net: &mut UnscoredTrainingNetwork| {
+        match net.reset_and_evaluate(&vec![1.0]) {
+            Ok(ref result) if result[0] > 0.0 => result[0],
+            _ => Score::NEG_INFINITY
+        }
+    };
+    let parameters = TrainingParameters { population_size: 10, seed: Some(5), ..TrainingParameters::default() };
+    let mut trainer = Trainer::new(parameters, 1, 1, fitness);
+    trainer.next_generation();
+    assert!(trainer.species.iter().flat_map(|species| species.networks.iter()).any(|net| net.score == Score::NEG_INFINITY));
+
+    let path = env::temp_dir().join(format!("neat_trainer_infinite_{}.json", ::std::process::id()));
+    trainer.checkpoint(&path).unwrap();
+    let mut resumed = Trainer::resume(&path, fitness).unwrap();
+    fs::remove_file(&path).unwrap();
+    let scores = |trainer: &Trainer<_>| {
+        trainer.species.iter().flat_map(|species| species.networks.iter().map(|net| (net.score, net.fitness))).collect::<Vec<_>>()
+    };
+    assert_eq!(scores(&resumed), scores(&trainer));
+
+    trainer.next_generation();
+    resumed.next_generation();
+    assert_eq!(scores(&resumed), scores(&trainer));
+}
+
+#[test]
+fn parallel_scoring_is_reproducible() {
+    let run = |threads| {
+        let parameters = TrainingParameters { population_size: 20, seed: Some(7), threads: threads, ..TrainingParameters::default() };
+        let mut trainer = Trainer::new(parameters, 2, 1, |net: &mut UnscoredTrainingNetwork| {
+            match net.reset_and_evaluate(&vec![1.0, 0.5]) {
+                Ok(result) => 1.0 / (1.0 + (result[0] - 0.25).abs()),
+                Err(_) => 0.0
+            }
+        });
+        for _ in 0..5 {
+            trainer.next_generation();
+        }
+        trainer.species.iter().map(|species| {
+            species.networks.iter().map(|net| (net.network.clone(), net.score)).collect::<Vec<_>>()
+        }).collect::<Vec<_>>()
+    };
+    assert_eq!(run(1), run(4));
+}
@@ -1,15 +1,25 @@
-use rand::{thread_rng, Rng};
+use rand::Rng;
 
-use neatwork::{Float, EvaluationError, Network, NID, GID, Gene, Node};
-use trainer::{Score, TrainingParameters, Probability};
+use neatwork::{Float, EvaluationError, Network, NID, GID, Gene, Gate, Node, MemoryCell};
+use trainer::{Score, TrainingParameters, SpeciationParameters, GeneMutationParameters, Probability};
 
-#[derive(Debug, Clone)]
+/// Network of the population together with its fitness
+#[derive(Debug, Clone, RustcDecodable, RustcEncodable)]
 pub struct ScoredTrainingNetwork {
     pub network: Network,
+    /// Score of the fitness closure
     pub score: Score,
-    global_rank: usize
+    /// Transformed score that selection works with (see FitnessTransform)
+    pub fitness: Score
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CrossoverError {
+    /// The parents have a different amount of inputs or outputs (inputs and outputs of both parents)
+    IoSizeMismatch((usize, usize), (usize, usize))
 }
 
+/// Offspring that still has to be scored by the fitness closure
 #[derive(Debug, Clone)]
 pub struct UnscoredTrainingNetwork {
     pub network: Network
@@ -26,11 +36,7 @@ impl ScoredTrainingNetwork {
         self.network.evaluate(inputs)
     }
 
-    pub fn is_compatible_with(&self, other: &ScoredTrainingNetwork) -> bool {
-        const C1: f64 = 1.0;
-        const C2: f64 = 0.4;
-        const DELTA_MAX: f64 = 10.0;//3.0;
-
+    pub fn is_compatible_with(&self, other: &ScoredTrainingNetwork, parameters: &SpeciationParameters) -> bool {
         let (net1, net2) = if self.network.genome.len() > other.network.genome.len() { (&self, &other) } else { (&other, &self) };
 
         let n = net1.network.genome.len() as f64;
@@ -55,12 +61,18 @@ impl ScoredTrainingNetwork {
             w /= tmp;
         }
 
-        d as f64 * C1/n + w * C2 < DELTA_MAX
+        d as f64 * parameters.disjoint_coefficient/n + w * parameters.weight_coefficient < parameters.threshold
+    }
+
+    /// Whether both networks have the same amount of inputs and outputs, which crossover() requires
+    pub fn can_cross_with(&self, other: &ScoredTrainingNetwork) -> bool {
+        self.network.inputs == other.network.inputs && self.network.outputs.len() == other.network.outputs.len()
     }
 
-    pub fn crossover(&self, other: &ScoredTrainingNetwork, self_is_fitter: bool) -> UnscoredTrainingNetwork {
-        if self.network.inputs != other.network.inputs || self.network.outputs.len() != other.network.outputs.len() {
-            panic!("IO Size mismatch on crossover")
+    pub fn crossover<R: Rng>(&self, other: &ScoredTrainingNetwork, self_is_fitter: bool, parameters: &GeneMutationParameters, rng: &mut R) -> Result<UnscoredTrainingNetwork, CrossoverError> {
+        if !self.can_cross_with(other) {
+            return Err(CrossoverError::IoSizeMismatch((self.network.inputs, self.network.outputs.len()),
+                                                      (other.network.inputs, other.network.outputs.len())));
         }
 
         let (mut child, other) = if self_is_fitter { (self.network.clone(), other) } else { (other.network.clone(), self) };
@@ -68,13 +80,13 @@ impl ScoredTrainingNetwork {
         for gene in child.genome.iter_mut() {
             match other.network.genome.iter().find(|other_gene| other_gene == &gene) {
                 Some(other_gene) => {
-                    gene.merge(other_gene)
+                    gene.merge_with(rng, other_gene, parameters.fitter_weight_probability as Float)
                 },
                 None => {}
             }
         }
 
-        UnscoredTrainingNetwork::new(child)
+        Ok(UnscoredTrainingNetwork::new(child))
     }
 
     pub fn get_weight_of(&self, other_gene: &Gene) -> Option<Float> {
@@ -104,14 +116,12 @@ impl UnscoredTrainingNetwork {
         self.network.evaluate(inputs)
     }
 
-    fn add_connection(&mut self, src: NID, dest: NID, weight: Option<Float>) {
-        let weight = match weight {
-            Some(w) => w,
-            None => thread_rng().gen::<Float>()*2.0 - 1.0
+    fn add_connection(&mut self, src: NID, dest: NID, gate: Option<Gate>, weight: Float) {
+        let new_gene = match gate {
+            Some(gate) => Gene::gate_with_weight(src, dest, gate, weight),
+            None => Gene::with_weight(src, dest, false, weight)
         };
 
-        let new_gene = Gene::with_weight(src, dest, false, weight);
-
         if match self.network.genome.iter_mut().find(|gene| gene == &&new_gene) {
             Some(gene) => {
                 gene.enable();
@@ -123,42 +133,76 @@ impl UnscoredTrainingNetwork {
         };
     }
 
+    /// Splits a gene by inserting a new node. If the gene fed into a gate the new connection to the target keeps feeding that gate.
     pub fn add_node_in_gene(&mut self, gene_id: GID) {
-        let (link, weight) = match self.network.genome.get_mut(gene_id) {
-            Some(gene) => (gene.link, gene.weight),
+        let (link, gate, weight) = match self.network.genome.get_mut(gene_id) {
+            Some(gene) => (gene.link, gene.gate, gene.weight),
             None => { panic!("Gene non existent") }
         };
 
         let node_id = self.network.nodes.len();
         self.network.nodes.push(Node::new());
 
-        self.add_connection(link.0, node_id, Some(1.0));
-        self.add_connection(node_id, link.1, Some(weight));
+        self.add_connection(link.0, node_id, None, 1.0);
+        self.add_connection(node_id, link.1, gate, weight);
 
         self.network.genome[gene_id].disable();
     }
 
-    pub fn mutate(&mut self, parameters: &TrainingParameters) {
-        for _ in 0..thread_rng().gen_range(1, 2) {
-            if thread_rng().gen::<Probability>() < parameters.add_gene_probability {
-                let src = thread_rng().gen_range(0, self.network.nodes.len());
-                let dest = thread_rng().gen_range(0, self.network.nodes.len());
-                self.add_connection(src, dest, None);
+    /// Turns a hidden node into a memory cell and connects each of its gates to a random node
+    ///
+    /// The aggregation, activation and bias of the node are kept.
+    pub fn add_memory_cell<R: Rng>(&mut self, node_id: NID, rng: &mut R) {
+        if self.network.nodes[node_id].is_memory() { return }
+        self.network.nodes[node_id].memory = Some(MemoryCell::new());
+
+        for gate in Gate::all().iter() {
+            let src = rng.gen_range(0, self.network.nodes.len());
+            let weight = Gene::random_weight(rng);
+            self.add_connection(src, node_id, Some(*gate), weight);
+        }
+    }
+
+    fn get_hidden_nodes(&self) -> Vec<NID> {
+        (self.network.inputs..self.network.nodes.len()).filter(|nid| !self.network.outputs.contains(nid)).collect()
+    }
+
+    pub fn mutate<R: Rng>(&mut self, parameters: &TrainingParameters, rng: &mut R) {
+        for _ in 0..rng.gen_range(1, 2) {
+            if rng.gen::<Probability>() < parameters.add_gene_probability {
+                let src = rng.gen_range(0, self.network.nodes.len());
+                let dest = rng.gen_range(0, self.network.nodes.len());
+                let gate = if self.network.nodes[dest].is_memory() {
+                    *rng.choose(&[None, Some(Gate::Input), Some(Gate::Forget), Some(Gate::Output)]).unwrap()
+                } else { None };
+                let weight = Gene::random_weight(rng);
+                self.add_connection(src, dest, gate, weight);
             }
-            if thread_rng().gen::<Probability>() < parameters.add_node_probability {
-                let gene_id = thread_rng().gen_range(0, self.network.genome.len());
+            if rng.gen::<Probability>() < parameters.add_node_probability {
+                let gene_id = rng.gen_range(0, self.network.genome.len());
                 self.add_node_in_gene(gene_id);
             }
-            if thread_rng().gen::<Probability>() < parameters.mutate_gene_probability {
-                let gene_id = thread_rng().gen_range(0, self.network.genome.len());
-                self.network.genome[gene_id].mutate();
+            if rng.gen::<Probability>() < parameters.memory_cell_probability {
+                let hidden_nodes = self.get_hidden_nodes();
+                if let Some(node_id) = rng.choose(&hidden_nodes) {
+                    self.add_memory_cell(*node_id, rng);
+                }
+            }
+            if rng.gen::<Probability>() < parameters.mutate_aggregation_probability {
+                let node_id = rng.gen_range(self.network.inputs, self.network.nodes.len());
+                self.network.nodes[node_id].mutate_aggregation_with_rng(rng);
+            }
+            if rng.gen::<Probability>() < parameters.mutate_gene_probability {
+                let gene_id = rng.gen_range(0, self.network.genome.len());
+                let mutation = &parameters.gene_mutation;
+                self.network.genome[gene_id].mutate_with(rng, mutation.reset_probability as Float, mutation.strength);
             }
-            if thread_rng().gen::<Probability>() < parameters.gene_enable_probability {
-                let gene_id = thread_rng().gen_range(0, self.network.genome.len());
+            if rng.gen::<Probability>() < parameters.gene_enable_probability {
+                let gene_id = rng.gen_range(0, self.network.genome.len());
                 self.network.genome[gene_id].enable();
             }
-            if thread_rng().gen::<Probability>() < parameters.gene_disable_probability {
-                let gene_id = thread_rng().gen_range(0, self.network.genome.len());
+            if rng.gen::<Probability>() < parameters.gene_disable_probability {
+                let gene_id = rng.gen_range(0, self.network.genome.len());
                 self.network.genome[gene_id].disable();
             }
         }
@@ -169,11 +213,12 @@ impl UnscoredTrainingNetwork {
         ScoredTrainingNetwork {
             network: self.network,
             score: score,
-            global_rank: 0
+            fitness: score
         }
     }
 }
 
+#[test]
 fn add_node() {
     let mut net = UnscoredTrainingNetwork::new(Network::new_empty(5, 1));
     let gene_count = net.network.genome.len();
@@ -187,11 +232,31 @@ fn add_node() {
 }
 
 #[test]
-#[should_panic]
 fn crossover_io_size_mismatch() {
     let net1 = UnscoredTrainingNetwork::new(Network::new_empty(5, 1)).calculate_score(&(|_| 0.0));
     let net2 = UnscoredTrainingNetwork::new(Network::new_empty(5, 2)).calculate_score(&(|_| 0.0));
-    net1.crossover(&net2, false);
+    let result = net1.crossover(&net2, false, &GeneMutationParameters::default(), &mut ::rand::thread_rng());
+    assert_eq!(result.unwrap_err(), CrossoverError::IoSizeMismatch((5, 1), (5, 2)));
+    assert!(net1.crossover(&net1, false, &GeneMutationParameters::default(), &mut ::rand::thread_rng()).is_ok());
+}
+
+#[test]
+fn crossover_matches_gates() {
+    let mut net1 = UnscoredTrainingNetwork::new(Network::new_empty(1, 1));
+    net1.network.genome[0].weight = 1.0;
+    net1.add_connection(0, 1, Some(Gate::Forget), 2.0);
+    let mut net2 = UnscoredTrainingNetwork::new(Network::new_empty(1, 1));
+    net2.network.genome[0].weight = -1.0;
+    net2.add_connection(0, 1, Some(Gate::Input), -2.0);
+    assert_eq!(net1.network.genome.len(), 2);
+
+    let net1 = net1.calculate_score(&(|_| 0.0));
+    let net2 = net2.calculate_score(&(|_| 0.0));
+    let parameters = GeneMutationParameters { fitter_weight_probability: 0.0, ..GeneMutationParameters::default() };
+    let child = net1.crossover(&net2, true, &parameters, &mut ::rand::thread_rng()).unwrap().network;
+    // Only the regular genes match, so the forget gate gene keeps the weight of the fitter parent
+    let weights = child.genome.iter().map(|gene| (gene.link, gene.gate, gene.weight)).collect::<Vec<_>>();
+    assert_eq!(weights, vec![((0, 1), None, -1.0), ((0, 1), Some(Gate::Forget), 2.0)]);
 }
 
 #[test]
@@ -199,17 +264,18 @@ fn compatibility() {
     let net1 = UnscoredTrainingNetwork::new(Network::new_empty(1, 1)).calculate_score(&(|_| 0.0));
     let net2 = UnscoredTrainingNetwork::new(Network::new_empty(1, 1)).calculate_score(&(|_| 0.0));
     let net3 = UnscoredTrainingNetwork::new(Network::new_empty(9, 8)).calculate_score(&(|_| 0.0));
-    assert!(net1.is_compatible_with(&net2));
-    assert!(!net1.is_compatible_with(&net3));
+    let parameters = SpeciationParameters::default();
+    assert!(net1.is_compatible_with(&net2, &parameters));
+    assert!(!net1.is_compatible_with(&net3, &parameters));
 }
 
 #[test]
 fn dedup_genome() {
     let mut net = UnscoredTrainingNetwork::new(Network::new_empty(5, 1));
     let genome_length = net.network.genome.len();
-    net.add_connection(2, 2, None);
+    net.add_connection(2, 2, None, 0.5);
     assert_eq!(net.network.genome.len(), genome_length+1);
-    net.add_connection(2, 2, None);
+    net.add_connection(2, 2, None, -0.5);
     assert_eq!(net.network.genome.len(), genome_length+1);
 }
 
@@ -218,6 +284,26 @@ fn reenabling_gene() {
     let mut net = UnscoredTrainingNetwork::new(Network::new_empty(5, 1));
     let link = net.network.genome[0].link;
     net.network.genome[0].disable();
-    net.add_connection(link.0, link.1, None);
+    net.add_connection(link.0, link.1, None, 0.5);
     assert!(!net.network.genome[0].disabled);
 }
+
+#[test]
+fn split_gate_gene() {
+    use neatwork::Aggregation;
+
+    let mut net = UnscoredTrainingNetwork::new(Network::new_empty(1, 1));
+    net.add_node_in_gene(0);
+    net.network.nodes[2].aggregation = Aggregation::Max;
+    net.network.nodes[2].bias = 0.5;
+    net.add_memory_cell(2, &mut ::rand::thread_rng());
+    assert!(net.network.nodes[2].is_memory());
+    assert_eq!((net.network.nodes[2].aggregation, net.network.nodes[2].bias), (Aggregation::Max, 0.5));
+    assert_eq!(net.network.genome.iter().filter(|gene| gene.gate.is_some()).count(), 3);
+
+    let gate_gene_id = net.network.genome.iter().position(|gene| gene.gate == Some(Gate::Forget)).unwrap();
+    net.add_node_in_gene(gate_gene_id);
+    let new_gene = net.network.genome.last().unwrap();
+    assert_eq!(new_gene.link, (3, 2));
+    assert_eq!(new_gene.gate, Some(Gate::Forget));
+}